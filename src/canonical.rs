@@ -0,0 +1,437 @@
+use crate::error::{Kind, OrcaError, Result};
+use serde::{
+    ser::{
+        self, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize,
+};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter, Write},
+};
+
+/// Intermediate tree produced from any `Serialize` implementation. Rendering it with `Display`
+/// yields the canonical encoding documented on `model::to_canonical`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Canonical {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Sequence(Vec<Self>),
+    Map(BTreeMap<String, Self>),
+}
+
+impl Canonical {
+    /// Convert any serializable value into its canonical tree.
+    pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> Result<Self> {
+        value.serialize(CanonicalSerializer)
+    }
+}
+
+impl Display for Canonical {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Number(value) => f.write_str(value),
+            Self::String(value) => write_string(f, value),
+            Self::Sequence(items) => {
+                f.write_char('[')?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            }
+            Self::Map(entries) => {
+                f.write_char('{')?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut Formatter, value: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for character in value.chars() {
+        match character {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            control if (control as u32) < 0x20 => {
+                write!(f, "\\u{:04x}", control as u32)?;
+            }
+            other => f.write_char(other)?,
+        }
+    }
+    f.write_char('"')
+}
+
+fn float<T: Display>(value: T, is_finite: bool) -> Result<Canonical> {
+    if is_finite {
+        Ok(Canonical::Number(value.to_string()))
+    } else {
+        Err(OrcaError::from(Kind::CanonicalEncoding(format!(
+            "Non-finite float `{value}` has no canonical encoding"
+        ))))
+    }
+}
+
+fn tagged(variant: &str, value: Canonical) -> Canonical {
+    Canonical::Map(BTreeMap::from([(variant.to_owned(), value)]))
+}
+
+struct CanonicalSerializer;
+
+#[expect(
+    clippy::renamed_function_params,
+    reason = "Serde names scalar parameters `v`, which is below the minimum identifier length."
+)]
+impl ser::Serializer for CanonicalSerializer {
+    type Ok = Canonical;
+    type Error = OrcaError;
+    type SerializeSeq = SequenceBuilder;
+    type SerializeTuple = SequenceBuilder;
+    type SerializeTupleStruct = SequenceBuilder;
+    type SerializeTupleVariant = SequenceBuilder;
+    type SerializeMap = MapBuilder;
+    type SerializeStruct = MapBuilder;
+    type SerializeStructVariant = MapBuilder;
+
+    fn serialize_bool(self, value: bool) -> Result<Canonical> {
+        Ok(Canonical::Bool(value))
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Canonical> {
+        Ok(Canonical::Number(value.to_string()))
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Canonical> {
+        Ok(Canonical::Number(value.to_string()))
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Canonical> {
+        Ok(Canonical::Number(value.to_string()))
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Canonical> {
+        Ok(Canonical::Number(value.to_string()))
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<Canonical> {
+        Ok(Canonical::Number(value.to_string()))
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Canonical> {
+        Ok(Canonical::Number(value.to_string()))
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Canonical> {
+        Ok(Canonical::Number(value.to_string()))
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Canonical> {
+        Ok(Canonical::Number(value.to_string()))
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Canonical> {
+        Ok(Canonical::Number(value.to_string()))
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<Canonical> {
+        Ok(Canonical::Number(value.to_string()))
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Canonical> {
+        float(value, value.is_finite())
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Canonical> {
+        float(value, value.is_finite())
+    }
+
+    fn serialize_char(self, value: char) -> Result<Canonical> {
+        Ok(Canonical::String(value.to_string()))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Canonical> {
+        Ok(Canonical::String(value.to_owned()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Canonical> {
+        Ok(Canonical::Sequence(
+            value
+                .iter()
+                .map(|byte| Canonical::Number(byte.to_string()))
+                .collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Canonical> {
+        Ok(Canonical::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Canonical> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Canonical> {
+        Ok(Canonical::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Canonical> {
+        Ok(Canonical::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Canonical> {
+        Ok(Canonical::String(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Canonical> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Canonical> {
+        Ok(tagged(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SequenceBuilder> {
+        Ok(SequenceBuilder {
+            variant: None,
+            items: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SequenceBuilder> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SequenceBuilder> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SequenceBuilder> {
+        Ok(SequenceBuilder {
+            variant: Some(variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapBuilder> {
+        Ok(MapBuilder {
+            variant: None,
+            entries: BTreeMap::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapBuilder> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<MapBuilder> {
+        Ok(MapBuilder {
+            variant: Some(variant),
+            entries: BTreeMap::new(),
+            next_key: None,
+        })
+    }
+}
+
+struct SequenceBuilder {
+    variant: Option<&'static str>,
+    items: Vec<Canonical>,
+}
+
+impl SequenceBuilder {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.items.push(Canonical::from_serialize(value)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Canonical {
+        let sequence = Canonical::Sequence(self.items);
+        match self.variant {
+            Some(variant) => tagged(variant, sequence),
+            None => sequence,
+        }
+    }
+}
+
+impl SerializeSeq for SequenceBuilder {
+    type Ok = Canonical;
+    type Error = OrcaError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Canonical> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTuple for SequenceBuilder {
+    type Ok = Canonical;
+    type Error = OrcaError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Canonical> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTupleStruct for SequenceBuilder {
+    type Ok = Canonical;
+    type Error = OrcaError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Canonical> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTupleVariant for SequenceBuilder {
+    type Ok = Canonical;
+    type Error = OrcaError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Canonical> {
+        Ok(self.finish())
+    }
+}
+
+struct MapBuilder {
+    variant: Option<&'static str>,
+    entries: BTreeMap<String, Canonical>,
+    next_key: Option<String>,
+}
+
+impl MapBuilder {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<()> {
+        self.entries.insert(key, Canonical::from_serialize(value)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Canonical {
+        let map = Canonical::Map(self.entries);
+        match self.variant {
+            Some(variant) => tagged(variant, map),
+            None => map,
+        }
+    }
+}
+
+impl SerializeMap for MapBuilder {
+    type Ok = Canonical;
+    type Error = OrcaError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        match Canonical::from_serialize(key)? {
+            Canonical::String(key_string) => {
+                self.next_key = Some(key_string);
+                Ok(())
+            }
+            other @ (Canonical::Null
+            | Canonical::Bool(_)
+            | Canonical::Number(_)
+            | Canonical::Sequence(_)
+            | Canonical::Map(_)) => Err(OrcaError::from(Kind::CanonicalEncoding(format!(
+                "Map key `{other}` is not a string"
+            )))),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self.next_key.take().ok_or_else(|| {
+            OrcaError::from(Kind::CanonicalEncoding(
+                "Map value serialized before its key".to_owned(),
+            ))
+        })?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Canonical> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeStruct for MapBuilder {
+    type Ok = Canonical;
+    type Error = OrcaError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<Canonical> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeStructVariant for MapBuilder {
+    type Ok = Canonical;
+    type Error = OrcaError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<Canonical> {
+        Ok(self.finish())
+    }
+}
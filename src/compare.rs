@@ -0,0 +1,283 @@
+use crate::{error::Result, util::get_type_name};
+use colored::Colorize;
+use serde::Serialize;
+use serde_yaml::Value;
+use std::fmt::{self, Display, Formatter};
+
+/// What happened to a field between two versions of a model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Field only the new model has, with its value.
+    Added(Value),
+    /// Field only the old model has, with its value.
+    Removed(Value),
+    /// Field both models have with different values as (old, new).
+    Changed(Value, Value),
+}
+
+/// Which part of a model a field belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeCategory {
+    /// Part of the hash, changing it makes a different model.
+    Hashed,
+    /// Part of the annotation.
+    Annotation,
+    /// Secret references, which are left out of the hash but aren't annotation either.
+    Secret,
+}
+
+impl ChangeCategory {
+    /// Category of a top-level field of a model.
+    fn of(field: &str) -> Self {
+        match field {
+            "annotation" => Self::Annotation,
+            "secret_env" => Self::Secret,
+            _ => Self::Hashed,
+        }
+    }
+
+    /// Heading changes of this category are listed under.
+    const fn heading(self) -> &'static str {
+        match self {
+            Self::Hashed => "Hash-affecting changes:",
+            Self::Annotation => "Annotation-only changes:",
+            Self::Secret => "Secret reference changes:",
+        }
+    }
+}
+
+/// A single field that differs between two models.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    /// Dot-separated path to the field with sequence indices in brackets e.g.
+    /// `input_stream_map.painting.path` or `edges[0].target`.
+    pub path: String,
+    /// How the field changed.
+    pub kind: ChangeKind,
+    /// Which part of the model the field belongs to.
+    pub category: ChangeCategory,
+}
+
+/// Field by field differences between two models of the same type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelDiff {
+    /// Lowercase model type name e.g. `pod`.
+    pub class: String,
+    /// Hash of the old model.
+    pub old_hash: String,
+    /// Hash of the new model.
+    pub new_hash: String,
+    /// Every differing field in field order, empty if the models are identical annotation
+    /// included.
+    pub changes: Vec<FieldChange>,
+}
+
+impl ModelDiff {
+    /// Changes that give the new model a different hash.
+    pub fn hash_changes(&self) -> impl Iterator<Item = &FieldChange> {
+        self.changes_in(ChangeCategory::Hashed)
+    }
+
+    /// Changes to the annotation.
+    pub fn annotation_changes(&self) -> impl Iterator<Item = &FieldChange> {
+        self.changes_in(ChangeCategory::Annotation)
+    }
+
+    /// Changes to secret references.
+    pub fn secret_changes(&self) -> impl Iterator<Item = &FieldChange> {
+        self.changes_in(ChangeCategory::Secret)
+    }
+
+    fn changes_in(&self, category: ChangeCategory) -> impl Iterator<Item = &FieldChange> {
+        self.changes
+            .iter()
+            .filter(move |change| change.category == category)
+    }
+}
+
+impl Display for ModelDiff {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} -> {}",
+            self.class,
+            self.old_hash.bright_cyan(),
+            self.new_hash.bright_cyan()
+        )?;
+        for category in [
+            ChangeCategory::Hashed,
+            ChangeCategory::Annotation,
+            ChangeCategory::Secret,
+        ] {
+            let mut changes = self.changes_in(category).peekable();
+            if changes.peek().is_some() {
+                write!(f, "\n{}", category.heading())?;
+            }
+            for change in changes {
+                write!(f, "\n  {change}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for FieldChange {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self.kind {
+            ChangeKind::Added(value) => {
+                write!(f, "{} {}: {}", "+".green(), self.path, render(value))
+            }
+            ChangeKind::Removed(value) => {
+                write!(f, "{} {}: {}", "-".red(), self.path, render(value))
+            }
+            ChangeKind::Changed(old, new) => write!(
+                f,
+                "{} {}: {} -> {}",
+                "~".yellow(),
+                self.path,
+                render(old),
+                render(new)
+            ),
+        }
+    }
+}
+
+/// Compare two models of the same type field by field.
+///
+/// Maps (e.g. `input_stream_map`), sequences and enum variants are descended into. A field that is
+/// `None` on one side counts as added or removed. The models' own hashes are reported on the diff
+/// instead of as a change.
+///
+/// # Errors
+///
+/// Will return `Err` if either model can't be serialized.
+pub fn diff<T: Serialize>(old: &T, new: &T) -> Result<ModelDiff> {
+    let old_value = serde_yaml::to_value(old)?;
+    let new_value = serde_yaml::to_value(new)?;
+    let hash_of = |value: &Value| {
+        value
+            .get("hash")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned()
+    };
+
+    let mut changes = Vec::new();
+    for field in field_names(&old_value, &new_value) {
+        if field == "hash" {
+            continue;
+        }
+        diff_values(
+            &field,
+            old_value.get(&field).unwrap_or(&Value::Null),
+            new_value.get(&field).unwrap_or(&Value::Null),
+            ChangeCategory::of(&field),
+            &mut changes,
+        );
+    }
+
+    Ok(ModelDiff {
+        class: get_type_name::<T>(),
+        old_hash: hash_of(&old_value),
+        new_hash: hash_of(&new_value),
+        changes,
+    })
+}
+
+fn diff_values(
+    path: &str,
+    old: &Value,
+    new: &Value,
+    category: ChangeCategory,
+    changes: &mut Vec<FieldChange>,
+) {
+    let mut push = |kind| {
+        changes.push(FieldChange {
+            path: path.to_owned(),
+            kind,
+            category,
+        });
+    };
+    match (old, new) {
+        _ if old == new => {}
+        (Value::Null, _) => push(ChangeKind::Added(new.clone())),
+        (_, Value::Null) => push(ChangeKind::Removed(old.clone())),
+        (Value::Mapping(_), Value::Mapping(_)) => {
+            for key in field_names(old, new) {
+                diff_values(
+                    &format!("{path}.{key}"),
+                    old.get(&key).unwrap_or(&Value::Null),
+                    new.get(&key).unwrap_or(&Value::Null),
+                    category,
+                    changes,
+                );
+            }
+        }
+        (Value::Sequence(old_items), Value::Sequence(new_items)) => {
+            for index in 0..old_items.len().max(new_items.len()) {
+                diff_values(
+                    &format!("{path}[{index}]"),
+                    old_items.get(index).unwrap_or(&Value::Null),
+                    new_items.get(index).unwrap_or(&Value::Null),
+                    category,
+                    changes,
+                );
+            }
+        }
+        (Value::Tagged(old_tagged), Value::Tagged(new_tagged))
+            if old_tagged.tag == new_tagged.tag =>
+        {
+            diff_values(
+                path,
+                &old_tagged.value,
+                &new_tagged.value,
+                category,
+                changes,
+            );
+        }
+        _ => push(ChangeKind::Changed(old.clone(), new.clone())),
+    }
+}
+
+/// Keys of two mappings in the order of `new` where keys only `old` has are placed after the key
+/// they follow in `old`.
+fn field_names(old: &Value, new: &Value) -> Vec<String> {
+    let keys = |value: &Value| match value {
+        Value::Mapping(mapping) => mapping
+            .keys()
+            .map(|key| key.as_str().map_or_else(|| render(key), str::to_owned))
+            .collect(),
+        Value::Null
+        | Value::Bool(_)
+        | Value::Number(_)
+        | Value::String(_)
+        | Value::Sequence(_)
+        | Value::Tagged(_) => Vec::new(),
+    };
+    let mut names = keys(new);
+    let mut previous = None;
+    for name in keys(old) {
+        if let Some(position) = names.iter().position(|existing| *existing == name) {
+            previous = Some(position);
+        } else {
+            let position = previous.map_or(0, |index| index + 1);
+            names.insert(position, name);
+            previous = Some(position);
+        }
+    }
+    names
+}
+
+/// A value on a single line, nested ones as JSON.
+fn render(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null | Value::Bool(_) | Value::Number(_) => serde_yaml::to_string(value)
+            .unwrap_or_default()
+            .trim_end()
+            .to_owned(),
+        Value::Sequence(_) | Value::Mapping(_) | Value::Tagged(_) => {
+            serde_json::to_string(value).unwrap_or_default()
+        }
+    }
+}
@@ -1,7 +1,11 @@
+use crate::migration::SCHEMA_VERSION;
 use anyhow;
 use colored::Colorize;
 use glob;
 use regex;
+use semver;
+use serde::ser;
+use serde_json;
 use serde_yaml;
 use std::{
     error::Error,
@@ -10,6 +14,7 @@ use std::{
     io,
     path::PathBuf,
 };
+use toml::{de::Error as TomlDeError, ser::Error as TomlSerError};
 /// Shorthand for a Result that returns an `OrcaError`.
 pub type Result<T> = anyhow::Result<T, OrcaError>;
 // pub type Result<T> = result::Result<T, OrcaError>;
@@ -21,8 +26,116 @@ pub(crate) enum Kind {
     FileExists(PathBuf),
     /// Returned if a file is expected to have a parent.
     FileHasNoParent(PathBuf),
-    /// Returned if an annotation was expected to exist.
-    NoAnnotationFound(String, String, String),
+    /// Returned if an annotation was expected to exist as (class, model id).
+    NoAnnotationFound(String, String),
+    /// Returned if a file in an annotation directory doesn't follow the `{name}-{version}.yaml`
+    /// naming.
+    InvalidAnnotationFile(PathBuf),
+    /// Returned if an annotation is already used by a model with another hash as (class,
+    /// annotation, hash).
+    AnnotationTaken(String, String, String),
+    /// Returned if an annotation name can't be stored as (name, reason).
+    InvalidAnnotationName(String, String),
+    /// Returned if a version is not a valid semantic version.
+    InvalidVersion(String, semver::Error),
+    /// Returned if a required field of a model was not set as (class, field).
+    MissingField(String, String),
+    /// Returned if a required text field of a model is blank as (class, field).
+    EmptyField(String, String),
+    /// Returned if a resource request isn't greater than zero as (field, value).
+    InvalidResource(String, String),
+    /// Returned if a stream's `match_pattern` is not a valid glob as (stream key, error).
+    InvalidMatchPattern(String, glob::PatternError),
+    /// Returned if an output stream resolves outside of `output_dir` as (stream key, path).
+    StreamPathOutsideOutputDir(String, PathBuf),
+    /// Returned if several output streams resolve to the same path as (path, stream keys).
+    DuplicateOutputPath(PathBuf, Vec<String>),
+    /// Returned if validation found more than one problem.
+    ValidationErrors(Vec<OrcaError>),
+    /// Returned if the keys of a stream map don't match what the pod declares as (map name,
+    /// missing keys, unexpected keys).
+    StreamKeyMismatch(String, Vec<String>, Vec<String>),
+    /// Returned if a resource quantity can't be parsed as (resource, quantity).
+    InvalidQuantity(String, String),
+    /// Returned if a compute capability isn't of the form `major.minor`.
+    InvalidComputeCapability(String),
+    /// Returned if a container image reference can't be parsed as (image, reason).
+    InvalidImageReference(String, String),
+    /// Returned if a store requires pinned images and a pod's image has no digest.
+    UnpinnedImage(String),
+    /// Returned if a source reference can't be parsed as (source, reason).
+    InvalidSourceReference(String, String),
+    /// Returned if a store requires pinned sources and a pod's source names a tag or branch, or
+    /// no ref at all, as (source, ref).
+    UnpinnedSource(String, Option<String>),
+    /// Returned if a command template has a placeholder for a stream the pod doesn't have as
+    /// (template, reason).
+    InvalidCommandTemplate(String, String),
+    /// Returned if a label selector can't be parsed as (selector, reason).
+    InvalidLabelSelector(String, String),
+    /// Returned if a stream's media type isn't of the form `type/subtype` as (key, media type).
+    InvalidMediaType(String, String),
+    /// Returned if the number of blobs bound to a stream doesn't match its cardinality as (key,
+    /// cardinality, count).
+    StreamCardinalityMismatch(String, String, usize),
+    /// Returned if a blob is a file where the stream's media type calls for a directory or vice
+    /// versa as (key, media type, blob location).
+    StreamBlobKindMismatch(String, String, PathBuf),
+    /// Returned if a pipeline edge connects streams that don't agree on what they carry as
+    /// (source, target, reason).
+    IncompatibleStreams(String, String, String),
+    /// Returned if an environment variable name isn't a valid POSIX name.
+    InvalidEnvName(String),
+    /// Returned if a pod sets an environment variable both directly and from a secret.
+    ConflictingEnvName(String),
+    /// Returned if a secret name contains anything but ASCII letters, digits, `-`, `_` and `.`
+    /// or starts with `.`.
+    InvalidSecretName(String),
+    /// Returned if a secret provider has no value for a secret as (name, provider).
+    SecretNotFound(String, String),
+    /// Returned if a model with secret references has no annotation to store them with as
+    /// (class).
+    UnannotatedSecretRefs(String),
+    /// Returned if a model with secret references is loaded by hash, which can't tell which
+    /// annotation's references to use, as (class, hash).
+    SecretRefsNeedAnnotation(String, String),
+    /// Returned if a pod job was not created from the pod given as (job's pod hash, pod hash).
+    JobPodMismatch(String, String),
+    /// Returned if a pod result ends before it starts as (start time, end time).
+    InvalidTimeRange(u64, u64),
+    /// Returned if a pipeline references a node that doesn't exist.
+    UnknownPipelineNode(String),
+    /// Returned if the pods of a pipeline are hashed with different algorithms as (algorithms).
+    MixedHashAlgorithms(Vec<String>),
+    /// Returned if a model is hashed with another algorithm than the store keys models by as
+    /// (class, hash, store's algorithm).
+    HashAlgorithmMismatch(String, String, String),
+    /// Returned if a pipeline references a stream key missing from a node's pod as (node, key).
+    DanglingStreamKey(String, String),
+    /// Returned if a pipeline leaves a node input without a source as (node, key).
+    UnconnectedInput(String, String),
+    /// Returned if a pipeline feeds a node input from more than one source as (node, key).
+    MultiplyConnectedInput(String, String),
+    /// Returned if a pipeline's edges form a cycle through the listed nodes.
+    PipelineCycle(Vec<String>),
+    /// Returned if a hash is prefixed with an unknown algorithm.
+    UnsupportedHashAlgorithm(String),
+    /// Returned if a spec's schema version is malformed or newer than this release supports as
+    /// (class, version).
+    UnsupportedSchemaVersion(String, String),
+    /// Returned if a hash prefix isn't a lowercase alphanumeric digest with an optional
+    /// `<algorithm>:` in front.
+    InvalidHashPrefix(String),
+    /// Returned if no stored model's hash starts with a prefix as (class, prefix).
+    NoHashMatch(String, String),
+    /// Returned if several stored models' hashes start with a prefix as (class, prefix,
+    /// candidates).
+    AmbiguousHashPrefix(String, String, Vec<String>),
+    /// Returned if a value can't be represented in the canonical encoding used for hashing.
+    CanonicalEncoding(String),
+    /// Returned if a stored spec no longer hashes to the hash it is stored under as (path,
+    /// expected, actual).
+    HashMismatch(PathBuf, String, String),
     /// Wrapper around `glob::GlobError`
     GlobError(glob::GlobError),
     /// Wrapper around `glob::PatternError`
@@ -31,6 +144,12 @@ pub(crate) enum Kind {
     RegexError(regex::Error),
     /// Wrapper around `serde_yaml::Error`
     SerdeYamlError(serde_yaml::Error),
+    /// Wrapper around `serde_json::Error`
+    SerdeJsonError(serde_json::Error),
+    /// Wrapper around `toml::de::Error`
+    TomlDeError(TomlDeError),
+    /// Wrapper around `toml::ser::Error`
+    TomlSerError(TomlSerError),
     /// Wrapper around `io::Error`
     IoError(io::Error),
 }
@@ -40,6 +159,7 @@ pub(crate) enum Kind {
 pub struct OrcaError(Kind);
 impl Error for OrcaError {}
 impl Display for OrcaError {
+    #[expect(clippy::too_many_lines, reason = "One arm per error kind.")]
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match &self.0 {
             Kind::FileExists(path) => {
@@ -56,17 +176,358 @@ impl Display for OrcaError {
                     path.to_string_lossy().bright_red()
                 )
             }
-            Kind::NoAnnotationFound(class, name, version) => {
-                write!(f, "No annotation found for `{name}:{version}` {class}.")
+            Kind::NoAnnotationFound(class, model_id) => {
+                write!(f, "No annotation found for `{model_id}` {class}.")
+            }
+            Kind::InvalidAnnotationFile(path) => {
+                write!(
+                    f,
+                    "Annotation file `{}` is not named as `{{name}}-{{version}}.yaml`.",
+                    path.to_string_lossy().bright_red()
+                )
+            }
+            Kind::AnnotationTaken(class, annotation, hash) => {
+                write!(
+                    f,
+                    "The {class} annotation `{}` is already taken by `{}`.",
+                    annotation.bright_red(),
+                    hash.bright_cyan()
+                )
+            }
+            Kind::InvalidAnnotationName(name, reason) => {
+                write!(
+                    f,
+                    "Annotation name `{}` is invalid: {reason}.",
+                    name.bright_red()
+                )
+            }
+            Kind::InvalidVersion(version, error) => {
+                write!(
+                    f,
+                    "Version `{}` is not a valid semantic version: {error}.",
+                    version.bright_red()
+                )
+            }
+            Kind::MissingField(class, field) => {
+                write!(
+                    f,
+                    "Missing required field `{}` for {class}.",
+                    field.bright_red()
+                )
+            }
+            Kind::EmptyField(class, field) => {
+                write!(
+                    f,
+                    "Field `{}` of {class} must not be empty.",
+                    field.bright_red()
+                )
+            }
+            Kind::InvalidResource(field, value) => {
+                write!(
+                    f,
+                    "Field `{}` must be greater than zero but got `{}`.",
+                    field.bright_cyan(),
+                    value.bright_red()
+                )
+            }
+            Kind::InvalidMatchPattern(key, error) => {
+                write!(
+                    f,
+                    "Match pattern of stream `{}` is not a valid glob: {error}.",
+                    key.bright_red()
+                )
+            }
+            Kind::StreamPathOutsideOutputDir(key, path) => {
+                write!(
+                    f,
+                    "Output stream `{}` path `{}` is outside of `output_dir`.",
+                    key.bright_cyan(),
+                    path.to_string_lossy().bright_red()
+                )
+            }
+            Kind::DuplicateOutputPath(path, keys) => {
+                write!(
+                    f,
+                    "Output streams [{}] share the same path `{}`.",
+                    keys.join(", ").bright_cyan(),
+                    path.to_string_lossy().bright_red()
+                )
+            }
+            Kind::ValidationErrors(problems) => {
+                write!(f, "Found {} problems:", problems.len())?;
+                for problem in problems {
+                    write!(f, "\n  - {problem}")?;
+                }
+                Ok(())
+            }
+            Kind::StreamKeyMismatch(map_name, missing, unexpected) => {
+                write!(
+                    f,
+                    "Keys in `{}` don't match the pod. Missing: [{}]. Unexpected: [{}].",
+                    map_name.bright_cyan(),
+                    missing.join(", "),
+                    unexpected.join(", ")
+                )
+            }
+            Kind::InvalidQuantity(resource, quantity) => {
+                write!(
+                    f,
+                    "`{}` is not a valid {resource} quantity.",
+                    quantity.bright_red()
+                )
+            }
+            Kind::InvalidComputeCapability(compute_capability) => {
+                write!(
+                    f,
+                    "`{}` is not a valid compute capability, expected `major.minor` e.g. `8.6`.",
+                    compute_capability.bright_red()
+                )
+            }
+            Kind::InvalidImageReference(image, reason) => {
+                write!(
+                    f,
+                    "Image `{}` is not a valid reference: {reason}.",
+                    image.bright_red()
+                )
+            }
+            Kind::UnpinnedImage(image) => {
+                write!(
+                    f,
+                    "Image `{}` is not pinned to an `@sha256:` digest.",
+                    image.bright_red()
+                )
+            }
+            Kind::InvalidSourceReference(source, reason) => {
+                write!(
+                    f,
+                    "Source `{}` is not a valid reference: {reason}.",
+                    source.bright_red()
+                )
+            }
+            Kind::UnpinnedSource(source, Some(git_ref)) => {
+                write!(
+                    f,
+                    "Source `{source}` names `{}` instead of a full commit SHA.",
+                    git_ref.bright_red()
+                )
+            }
+            Kind::UnpinnedSource(source, None) => {
+                write!(
+                    f,
+                    "Source `{}` doesn't name a commit SHA.",
+                    source.bright_red()
+                )
+            }
+            Kind::InvalidCommandTemplate(template, reason) => {
+                write!(
+                    f,
+                    "Command `{}` can't be expanded: {reason}.",
+                    template.bright_red()
+                )
+            }
+            Kind::InvalidLabelSelector(selector, reason) => {
+                write!(
+                    f,
+                    "Label selector `{}` is invalid: {reason}.",
+                    selector.bright_red()
+                )
+            }
+            Kind::InvalidMediaType(key, media_type) => {
+                write!(
+                    f,
+                    "Media type `{}` of stream `{key}` is not of the form `type/subtype`.",
+                    media_type.bright_red()
+                )
+            }
+            Kind::StreamCardinalityMismatch(key, cardinality, count) => {
+                write!(
+                    f,
+                    "Stream `{}` expects {cardinality} blob(s) but got {count}.",
+                    key.bright_red()
+                )
+            }
+            Kind::StreamBlobKindMismatch(key, media_type, location) => {
+                write!(
+                    f,
+                    "Blob `{}` doesn't fit stream `{key}` of media type `{media_type}`.",
+                    location.to_string_lossy().bright_red()
+                )
+            }
+            Kind::IncompatibleStreams(source, target, reason) => {
+                write!(
+                    f,
+                    "Stream `{}` can't feed `{}`: {reason}.",
+                    source.bright_cyan(),
+                    target.bright_red()
+                )
+            }
+            Kind::InvalidEnvName(name) => {
+                write!(
+                    f,
+                    "Environment variable name `{}` is not valid.",
+                    name.bright_red()
+                )
+            }
+            Kind::ConflictingEnvName(name) => {
+                write!(
+                    f,
+                    "Environment variable `{}` is set both directly and from a secret.",
+                    name.bright_red()
+                )
+            }
+            Kind::InvalidSecretName(name) => {
+                write!(f, "Secret name `{}` is not valid.", name.bright_red())
+            }
+            Kind::SecretNotFound(name, provider) => {
+                write!(
+                    f,
+                    "Secret `{}` was not found in {provider}.",
+                    name.bright_red()
+                )
+            }
+            Kind::UnannotatedSecretRefs(class) => {
+                write!(
+                    f,
+                    "The {} has secret references but no annotation to store them with.",
+                    class.bright_red()
+                )
+            }
+            Kind::SecretRefsNeedAnnotation(class, hash) => {
+                write!(
+                    f,
+                    "The {class} `{}` keeps its secret references with its annotations so it has \
+                     to be loaded by name.",
+                    hash.bright_red()
+                )
+            }
+            Kind::JobPodMismatch(job_pod_hash, pod_hash) => {
+                write!(
+                    f,
+                    "Pod job references pod `{}` but pod `{}` was given.",
+                    job_pod_hash.bright_cyan(),
+                    pod_hash.bright_red()
+                )
+            }
+            Kind::InvalidTimeRange(start_time, end_time) => {
+                write!(
+                    f,
+                    "Pod result ends at `{}` before it starts at `{}`.",
+                    end_time.to_string().bright_red(),
+                    start_time.to_string().bright_cyan()
+                )
+            }
+            Kind::MixedHashAlgorithms(algorithms) => {
+                write!(
+                    f,
+                    "Pipeline nodes are hashed with different algorithms: {}.",
+                    algorithms.join(", ").bright_red()
+                )
+            }
+            Kind::HashAlgorithmMismatch(class, hash, algorithm) => {
+                write!(
+                    f,
+                    "The {class} `{}` is hashed with another algorithm than the store keys models by \
+                     (`{}`), build it with that algorithm instead.",
+                    hash.bright_red(),
+                    algorithm.bright_cyan()
+                )
+            }
+            Kind::UnknownPipelineNode(node) => {
+                write!(f, "Pipeline has no node `{}`.", node.bright_red())
+            }
+            Kind::DanglingStreamKey(node, key) => {
+                write!(
+                    f,
+                    "Pipeline node `{}` has no stream `{}`.",
+                    node.bright_cyan(),
+                    key.bright_red()
+                )
+            }
+            Kind::UnconnectedInput(node, key) => {
+                write!(
+                    f,
+                    "Input `{}` of pipeline node `{}` is not connected.",
+                    key.bright_red(),
+                    node.bright_cyan()
+                )
+            }
+            Kind::MultiplyConnectedInput(node, key) => {
+                write!(
+                    f,
+                    "Input `{}` of pipeline node `{}` is connected more than once.",
+                    key.bright_red(),
+                    node.bright_cyan()
+                )
+            }
+            Kind::PipelineCycle(nodes) => {
+                write!(
+                    f,
+                    "Pipeline contains a cycle involving nodes [{}].",
+                    nodes.join(", ").bright_red()
+                )
+            }
+            Kind::UnsupportedHashAlgorithm(name) => {
+                write!(
+                    f,
+                    "Hash algorithm `{}` is not supported.",
+                    name.bright_red()
+                )
+            }
+            Kind::InvalidHashPrefix(hash_prefix) => {
+                write!(
+                    f,
+                    "Hash prefix `{}` is not a digest optionally prefixed with its algorithm e.g. `sha256:13d6`.",
+                    hash_prefix.bright_red()
+                )
+            }
+            Kind::NoHashMatch(class, hash_prefix) => {
+                write!(
+                    f,
+                    "No {class} found with a hash starting with `{}`.",
+                    hash_prefix.bright_red()
+                )
+            }
+            Kind::AmbiguousHashPrefix(class, hash_prefix, candidates) => {
+                write!(
+                    f,
+                    "Hash prefix `{}` is ambiguous for {class}, candidates: {}.",
+                    hash_prefix.bright_red(),
+                    candidates.join(", ").bright_cyan()
+                )
+            }
+            Kind::UnsupportedSchemaVersion(class, version) => {
+                write!(
+                    f,
+                    "Schema version `{}` of {class} spec is not supported, this release reads up to `{SCHEMA_VERSION}`.",
+                    version.bright_red()
+                )
+            }
+            Kind::CanonicalEncoding(message) => write!(f, "{message}."),
+            Kind::HashMismatch(path, expected, actual) => {
+                write!(
+                    f,
+                    "Spec `{}` hashes to `{}` instead of `{}`, the store is corrupted or was modified.",
+                    path.to_string_lossy().bright_cyan(),
+                    actual.bright_red(),
+                    expected.bright_cyan()
+                )
             }
             Kind::GlobError(error) => write!(f, "{error}"),
             Kind::GlobPaternError(error) => write!(f, "{error}"),
             Kind::SerdeYamlError(error) => write!(f, "{error}"),
+            Kind::SerdeJsonError(error) => write!(f, "{error}"),
+            Kind::TomlDeError(error) => write!(f, "{error}"),
+            Kind::TomlSerError(error) => write!(f, "{error}"),
             Kind::RegexError(error) => write!(f, "{error}"),
             Kind::IoError(error) => write!(f, "{error}"),
         }
     }
 }
+impl ser::Error for OrcaError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(Kind::CanonicalEncoding(msg.to_string()))
+    }
+}
 impl From<glob::GlobError> for OrcaError {
     fn from(error: glob::GlobError) -> Self {
         Self(Kind::GlobError(error))
@@ -82,6 +543,21 @@ impl From<serde_yaml::Error> for OrcaError {
         Self(Kind::SerdeYamlError(error))
     }
 }
+impl From<serde_json::Error> for OrcaError {
+    fn from(error: serde_json::Error) -> Self {
+        Self(Kind::SerdeJsonError(error))
+    }
+}
+impl From<TomlDeError> for OrcaError {
+    fn from(error: TomlDeError) -> Self {
+        Self(Kind::TomlDeError(error))
+    }
+}
+impl From<TomlSerError> for OrcaError {
+    fn from(error: TomlSerError) -> Self {
+        Self(Kind::TomlSerError(error))
+    }
+}
 impl From<regex::Error> for OrcaError {
     fn from(error: regex::Error) -> Self {
         Self(Kind::RegexError(error))
@@ -0,0 +1,252 @@
+use crate::{
+    error::{Kind, OrcaError, Result},
+    quantity::Memory,
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt::{self, Display, Formatter},
+    result,
+    str::FromStr,
+};
+
+/// Specification for GPU requirements in computation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GPURequirement {
+    /// Manufacturer of the cards, `Any` if it doesn't matter.
+    #[serde(default)]
+    pub vendor: GPUVendor,
+    /// Case-insensitive part of the card's model name e.g. `A100` or `MI250`, any model if
+    /// `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Minimum memory of each card.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_memory: Option<Memory>,
+    /// Minimum CUDA compute capability of each card e.g. `8.0`. Only cards that report one can
+    /// satisfy it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_compute_capability: Option<ComputeCapability>,
+    /// Number of GPU cards required.
+    pub count: u16,
+}
+
+impl GPURequirement {
+    /// Why `device` doesn't satisfy this requirement, empty if it does.
+    pub fn check_device(&self, device: &GPUDevice) -> Vec<GPUMismatch> {
+        let mut mismatches = Vec::new();
+        if self.vendor != GPUVendor::Any && self.vendor != device.vendor {
+            mismatches.push(GPUMismatch::Vendor(self.vendor, device.vendor));
+        }
+        if let Some(model) = &self.model {
+            if !device.model.to_lowercase().contains(&model.to_lowercase()) {
+                mismatches.push(GPUMismatch::Model(model.clone(), device.model.clone()));
+            }
+        }
+        if let Some(min_memory) = self.min_memory {
+            if device.memory < min_memory {
+                mismatches.push(GPUMismatch::Memory(min_memory, device.memory));
+            }
+        }
+        if let Some(min_compute_capability) = self.min_compute_capability {
+            if device
+                .compute_capability
+                .is_none_or(|compute_capability| compute_capability < min_compute_capability)
+            {
+                mismatches.push(GPUMismatch::ComputeCapability(
+                    min_compute_capability,
+                    device.compute_capability,
+                ));
+            }
+        }
+        mismatches
+    }
+
+    /// Check every device of a host's inventory against this requirement.
+    pub fn match_inventory(&self, inventory: &[GPUDevice]) -> GPUMatch {
+        GPUMatch {
+            count: self.count,
+            devices: inventory
+                .iter()
+                .map(|device| (device.clone(), self.check_device(device)))
+                .collect(),
+        }
+    }
+}
+
+/// GPU manufacturer.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GPUVendor {
+    /// Any manufacturer, only meaningful in a requirement.
+    #[default]
+    Any,
+    /// NVIDIA-manufactured card.
+    NVIDIA,
+    /// AMD-manufactured card.
+    AMD,
+    /// Intel-manufactured card.
+    Intel,
+}
+
+impl Display for GPUVendor {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Any => "any vendor",
+            Self::NVIDIA => "NVIDIA",
+            Self::AMD => "AMD",
+            Self::Intel => "Intel",
+        })
+    }
+}
+
+/// CUDA compute capability written as `{major}.{minor}` e.g. `8.6`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ComputeCapability {
+    /// Architecture generation.
+    pub major: u16,
+    /// Revision within the generation.
+    pub minor: u16,
+}
+
+impl FromStr for ComputeCapability {
+    type Err = OrcaError;
+
+    fn from_str(compute_capability: &str) -> Result<Self> {
+        compute_capability
+            .split_once('.')
+            .and_then(|(major, minor)| {
+                Some(Self {
+                    major: major.parse().ok()?,
+                    minor: minor.parse().ok()?,
+                })
+            })
+            .ok_or_else(|| {
+                OrcaError::from(Kind::InvalidComputeCapability(
+                    compute_capability.to_owned(),
+                ))
+            })
+    }
+}
+
+impl Display for ComputeCapability {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl Serialize for ComputeCapability {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ComputeCapability {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// A GPU installed on a host, as reported by whatever inventories the host.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GPUDevice {
+    /// Manufacturer of the card.
+    pub vendor: GPUVendor,
+    /// Full model name e.g. `NVIDIA A100-SXM4-80GB`.
+    pub model: String,
+    /// Memory of the card.
+    pub memory: Memory,
+    /// CUDA compute capability, `None` for cards without CUDA support.
+    pub compute_capability: Option<ComputeCapability>,
+}
+
+/// Reason a device doesn't satisfy a GPU requirement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GPUMismatch {
+    /// Made by another vendor as (required, found).
+    Vendor(GPUVendor, GPUVendor),
+    /// Model name doesn't contain the required one as (required, found).
+    Model(String, String),
+    /// Too little memory as (required, found).
+    Memory(Memory, Memory),
+    /// Compute capability too low or missing as (required, found).
+    ComputeCapability(ComputeCapability, Option<ComputeCapability>),
+}
+
+impl Display for GPUMismatch {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Vendor(required, found) => write!(f, "made by {found} instead of {required}"),
+            Self::Model(required, found) => {
+                write!(f, "model `{found}` doesn't match `{required}`")
+            }
+            Self::Memory(required, found) => {
+                write!(f, "has {found} of memory but {required} is required")
+            }
+            Self::ComputeCapability(required, Some(found)) => {
+                write!(f, "compute capability {found} is below {required}")
+            }
+            Self::ComputeCapability(required, None) => {
+                write!(f, "has no compute capability but {required} is required")
+            }
+        }
+    }
+}
+
+/// Outcome of matching a GPU requirement against a host's inventory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GPUMatch {
+    count: u16,
+    devices: Vec<(GPUDevice, Vec<GPUMismatch>)>,
+}
+
+impl GPUMatch {
+    /// Every device in inventory order along with why it doesn't qualify, empty if it does.
+    pub fn devices(&self) -> &[(GPUDevice, Vec<GPUMismatch>)] {
+        &self.devices
+    }
+
+    /// Inventory indices of the devices that satisfy the requirement.
+    pub fn qualifying(&self) -> Vec<usize> {
+        self.devices
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, mismatches))| mismatches.is_empty())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Whether enough devices qualify to meet the required count.
+    pub fn is_satisfied(&self) -> bool {
+        self.qualifying().len() >= usize::from(self.count)
+    }
+}
+
+impl Display for GPUMatch {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} GPU(s) qualify, {} required.",
+            self.qualifying().len(),
+            self.devices.len(),
+            self.count
+        )?;
+        for (index, (device, mismatches)) in self.devices.iter().enumerate() {
+            write!(f, "\n  - GPU {index} ({}): ", device.model)?;
+            if mismatches.is_empty() {
+                write!(f, "qualifies")?;
+            } else {
+                write!(
+                    f,
+                    "{}",
+                    mismatches
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
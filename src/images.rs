@@ -0,0 +1,197 @@
+use crate::error::{Kind, OrcaError, Result};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt::{self, Display, Formatter},
+    result,
+    str::FromStr,
+};
+
+/// Registry an image without one is pulled from.
+pub const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// A container image reference split into its parts e.g.
+/// `ghcr.io/zenml-io/zenml:0.67.0@sha256:1d3f…`.
+///
+/// The first path component is taken as the registry if it contains a `.` or `:` or is
+/// `localhost`, like Docker does. Written back exactly as parsed so parsing never changes a pod's
+/// hash.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageReference {
+    /// Registry host with an optional port, `None` for the default registry.
+    pub registry: Option<String>,
+    /// Slash-separated repository path e.g. `zenmldocker/zenml-server`.
+    pub repository: String,
+    /// Mutable tag e.g. `0.67.0`.
+    pub tag: Option<String>,
+    /// Immutable content digest e.g. `sha256:1d3f…`.
+    pub digest: Option<String>,
+}
+
+impl ImageReference {
+    /// Whether the reference names its content by digest, which a registry can't change.
+    pub const fn is_pinned(&self) -> bool {
+        self.digest.is_some()
+    }
+
+    /// Registry the image is pulled from, `DEFAULT_REGISTRY` if none is given.
+    pub fn registry_or_default(&self) -> &str {
+        self.registry.as_deref().unwrap_or(DEFAULT_REGISTRY)
+    }
+
+    /// Parse `image`, explaining what is wrong with it if it isn't a valid reference.
+    pub(crate) fn try_parse(image: &str) -> result::Result<Self, &'static str> {
+        let (name_tag, digest) = match image.split_once('@') {
+            Some((name_tag, digest)) if is_digest(digest) => (name_tag, Some(digest.to_owned())),
+            Some(_) => {
+                return Err(
+                    "digest must be `algorithm:hex` e.g. `sha256:` followed by 64 hex digits",
+                )
+            }
+            None => (image, None),
+        };
+
+        let (name, tag) = match name_tag.rsplit_once(':') {
+            Some((name, tag)) if !tag.contains('/') => {
+                if !is_tag(tag) {
+                    return Err("tag must be up to 128 of `[A-Za-z0-9_.-]`");
+                }
+                (name, Some(tag.to_owned()))
+            }
+            _ => (name_tag, None),
+        };
+
+        let (registry, repository) = match name.split_once('/') {
+            Some((host, path))
+                if host.contains(['.', ':']) || host == "localhost" || host.is_empty() =>
+            {
+                if !is_registry(host) {
+                    return Err("registry must be a host name with an optional port");
+                }
+                (Some(host.to_owned()), path)
+            }
+            _ => (None, name),
+        };
+        if !repository.split('/').all(is_path_component) {
+            return Err("repository must be lowercase alphanumeric components separated by `/`");
+        }
+
+        Ok(Self {
+            registry,
+            repository: repository.to_owned(),
+            tag,
+            digest,
+        })
+    }
+}
+
+impl FromStr for ImageReference {
+    type Err = OrcaError;
+
+    fn from_str(image: &str) -> Result<Self> {
+        Self::try_parse(image).map_err(|reason| {
+            OrcaError::from(Kind::InvalidImageReference(image.into(), reason.into()))
+        })
+    }
+}
+
+impl Display for ImageReference {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if let Some(registry) = &self.registry {
+            write!(f, "{registry}/")?;
+        }
+        write!(f, "{}", self.repository)?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{tag}")?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{digest}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for ImageReference {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ImageReference {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// `algorithm:hex` where sha256 digests have exactly 64 lowercase hex digits.
+fn is_digest(digest: &str) -> bool {
+    let Some((algorithm, encoded)) = digest.split_once(':') else {
+        return false;
+    };
+    let is_hex = encoded
+        .chars()
+        .all(|character| character.is_ascii_digit() || matches!(character, 'a'..='f'));
+    let is_algorithm = !algorithm.is_empty()
+        && algorithm.chars().all(|character| {
+            character.is_ascii_lowercase()
+                || character.is_ascii_digit()
+                || matches!(character, '+' | '.' | '_' | '-')
+        });
+    is_algorithm
+        && is_hex
+        && if algorithm == "sha256" {
+            encoded.len() == 64
+        } else {
+            encoded.len() >= 32
+        }
+}
+
+/// `[A-Za-z0-9_][A-Za-z0-9_.-]{0,127}`.
+fn is_tag(tag: &str) -> bool {
+    tag.len() <= 128
+        && !tag.starts_with(['.', '-'])
+        && !tag.is_empty()
+        && tag.chars().all(|character| {
+            character.is_ascii_alphanumeric() || matches!(character, '_' | '.' | '-')
+        })
+}
+
+/// Dot-separated host name labels with an optional `:port`.
+fn is_registry(registry: &str) -> bool {
+    let (host, port) = registry.split_once(':').unwrap_or((registry, "0"));
+    !port.is_empty()
+        && port.chars().all(|character| character.is_ascii_digit())
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .chars()
+                    .all(|character| character.is_ascii_alphanumeric() || character == '-')
+        })
+}
+
+/// Lowercase alphanumeric runs joined by single `.` or `_`, double `__` or any number of `-`.
+fn is_path_component(component: &str) -> bool {
+    let is_alphanumeric =
+        |character: char| character.is_ascii_lowercase() || character.is_ascii_digit();
+    let mut separator = String::new();
+    for character in component.chars() {
+        if is_alphanumeric(character) {
+            if !matches!(separator.as_str(), "" | "." | "_" | "__")
+                && !separator
+                    .chars()
+                    .all(|separator_char| separator_char == '-')
+            {
+                return false;
+            }
+            separator.clear();
+        } else if matches!(character, '.' | '_' | '-') {
+            separator.push(character);
+        } else {
+            return false;
+        }
+    }
+    component.starts_with(is_alphanumeric) && separator.is_empty()
+}
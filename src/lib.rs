@@ -1,10 +1,25 @@
 //! Intuitive compute pipeline orchestration with reproducibility, performance, and scalability in
 //! mind.
 
+mod canonical;
+/// Field by field comparison of models.
+pub mod compare;
 /// Error handling based on enumeration.
 pub mod error;
+/// GPU requirements and matching them against a host's GPUs.
+pub mod gpu;
+/// Container image references and digest pinning.
+pub mod images;
+/// Schema versioning and upgrades for stored specs.
+pub mod migration;
 /// Components of the data model.
 pub mod model;
+/// Resource quantities for CPU and memory.
+pub mod quantity;
+/// Secrets referenced by pods and the providers that resolve them.
+pub mod secrets;
+/// Source code references for pods.
+pub mod sources;
 /// Data persistence is provided by using a store backend.
 pub mod store;
 mod util;
@@ -0,0 +1,168 @@
+use crate::{
+    error::{Kind, OrcaError, Result},
+    model::SpecFormat,
+    quantity::{Cpu, Memory},
+    util::get_type_name,
+};
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+use std::fmt::Display;
+
+/// Schema version written into every spec by `to_yaml`. Specs from before versioning was
+/// introduced have no `schema` key and are treated as version `0`.
+///
+/// Bump this whenever the serialized shape of any model changes and register a `Migration` in
+/// `MIGRATIONS` for every class affected by the change.
+pub const SCHEMA_VERSION: u64 = 3;
+
+/// A step that upgrades the spec of one model class from schema version `from` to `from + 1`.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Lowercase model type name e.g. `pod`.
+    pub class: &'static str,
+    /// Schema version the spec is upgraded from.
+    pub from: u64,
+    /// Rewrites the spec mapping in place. Enum variants that carry data are YAML-tagged values
+    /// in YAML specs and single-key maps in JSON and TOML specs.
+    pub apply: fn(&mut Mapping) -> Result<()>,
+}
+
+/// Every migration shipped with orcapod. A class without a migration for a given version is
+/// unchanged by that version bump, e.g. `0` -> `1` only added the `schema` key itself.
+pub const MIGRATIONS: &[Migration] = &[
+    // `1` -> `2`: CPU and memory became quantity strings instead of (fractional) cores and bytes
+    Migration {
+        class: "pod",
+        from: 1,
+        apply: |spec_mapping| {
+            quantity_to_string::<Cpu>(spec_mapping, "recommended_cpus")?;
+            quantity_to_string::<Memory>(spec_mapping, "recommended_memory")
+        },
+    },
+    Migration {
+        class: "podjob",
+        from: 1,
+        apply: |spec_mapping| {
+            quantity_to_string::<Cpu>(spec_mapping, "cpu_limit")?;
+            quantity_to_string::<Memory>(spec_mapping, "memory_limit")
+        },
+    },
+    // `2` -> `3`: a GPU requirement names its vendor separately from an optional model and its
+    // memory became a minimum quantity
+    Migration {
+        class: "pod",
+        from: 2,
+        apply: |spec_mapping| {
+            if let Some(Value::Mapping(gpu_mapping)) = spec_mapping.get_mut("required_gpu") {
+                split_gpu_model(gpu_mapping);
+                if let Some(memory) = gpu_mapping.remove("recommended_memory") {
+                    gpu_mapping.insert("min_memory".into(), memory);
+                    quantity_to_string::<Memory>(gpu_mapping, "min_memory")?;
+                }
+            }
+            Ok(())
+        },
+    },
+];
+
+/// Rewrite a numeric quantity under `key` in its string form.
+fn quantity_to_string<T: DeserializeOwned + Display>(
+    spec_mapping: &mut Mapping,
+    key: &str,
+) -> Result<()> {
+    if let Some(value) = spec_mapping.get_mut(key) {
+        if value.is_number() {
+            *value = Value::from(serde_yaml::from_value::<T>(value.clone())?.to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite a GPU `model` written as a vendor variant e.g. `!NVIDIA A100` or `{NVIDIA: A100}` into
+/// separate `vendor` and `model` keys. Anything else is left for deserialization to reject.
+fn split_gpu_model(gpu_mapping: &mut Mapping) {
+    let variant = match gpu_mapping.get("model") {
+        Some(Value::Tagged(tagged)) => Some((
+            tagged.tag.to_string().trim_start_matches('!').to_owned(),
+            tagged.value.clone(),
+        )),
+        Some(Value::Mapping(variant_mapping)) if variant_mapping.len() == 1 => variant_mapping
+            .iter()
+            .next()
+            .and_then(|(vendor, model)| Some((vendor.as_str()?.to_owned(), model.clone()))),
+        _ => None,
+    };
+    if let Some((vendor, model)) = variant {
+        gpu_mapping.insert("vendor".into(), Value::from(vendor));
+        gpu_mapping.insert("model".into(), model);
+    }
+}
+
+/// Read the schema version of a spec, `0` if it has none.
+///
+/// # Errors
+///
+/// Will return `Err` if the version isn't a non-negative integer or is newer than
+/// `SCHEMA_VERSION` i.e. the spec was written by a later release.
+pub fn schema_version<T>(spec_mapping: &Mapping) -> Result<u64> {
+    let version = match spec_mapping.get("schema") {
+        None => 0,
+        Some(value) => value.as_u64().ok_or_else(|| {
+            OrcaError::from(Kind::UnsupportedSchemaVersion(
+                get_type_name::<T>(),
+                serde_yaml::to_string(value)
+                    .unwrap_or_default()
+                    .trim_end()
+                    .to_owned(),
+            ))
+        })?,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(OrcaError::from(Kind::UnsupportedSchemaVersion(
+            get_type_name::<T>(),
+            version.to_string(),
+        )));
+    }
+    Ok(version)
+}
+
+/// Upgrade a spec of model type `T` to `SCHEMA_VERSION` by applying each matching step from
+/// `migrations` in order. The `schema` key is updated to match. Returns whether the spec was
+/// upgraded.
+///
+/// # Errors
+///
+/// Will return `Err` if the spec's schema version is unsupported or a migration fails.
+pub fn migrate<T>(spec_mapping: &mut Mapping, migrations: &[Migration]) -> Result<bool> {
+    let class = get_type_name::<T>();
+    let version = schema_version::<T>(spec_mapping)?;
+    if version == SCHEMA_VERSION {
+        return Ok(false);
+    }
+
+    for from in version..SCHEMA_VERSION {
+        for migration in migrations
+            .iter()
+            .filter(|migration| migration.class == class && migration.from == from)
+        {
+            (migration.apply)(spec_mapping)?;
+        }
+    }
+    spec_mapping.insert("schema".into(), Value::from(SCHEMA_VERSION));
+    Ok(true)
+}
+
+/// Upgrade a spec of model type `T` written in `format` with the shipped `MIGRATIONS`. Returns
+/// the upgraded spec in the same format or `None` if it is already current.
+///
+/// # Errors
+///
+/// Will return `Err` if the spec is invalid, its schema version is unsupported or a migration
+/// fails.
+pub fn upgrade_spec<T>(spec: &str, format: SpecFormat) -> Result<Option<String>> {
+    let mut spec_mapping = format.parse(spec)?;
+    if !migrate::<T>(&mut spec_mapping, MIGRATIONS)? {
+        return Ok(None);
+    }
+    Ok(Some(format.render(spec_mapping)?))
+}
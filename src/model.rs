@@ -1,26 +1,316 @@
 use crate::{
-    error::Result,
-    util::{get_type_name, hash},
+    canonical::Canonical,
+    error::{Kind, OrcaError, Result},
+    gpu::GPURequirement,
+    images::ImageReference,
+    migration::{migrate, MIGRATIONS, SCHEMA_VERSION},
+    quantity::{Cpu, Memory},
+    secrets::{SecretProvider, SecretRef},
+    sources::SourceReference,
+    util::{get_type_name, normalize_path},
 };
+use chrono::{DateTime, Utc};
+pub use semver::{Version, VersionReq};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_yaml::{Mapping, Value};
-use std::{collections::BTreeMap, path::PathBuf};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+    ops,
+    path::PathBuf,
+    result, slice,
+    str::FromStr,
+};
+/// Text formats a model spec can be written in. Annotations are always stored as YAML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecFormat {
+    /// YAML, the format `LocalFileStore` writes.
+    Yaml,
+    /// JSON, pretty-printed.
+    Json,
+    /// TOML, where `None` fields are omitted since TOML has no null.
+    Toml,
+}
+
+impl SpecFormat {
+    /// Every format in the order a store looks for spec files.
+    pub const ALL: [Self; 3] = [Self::Yaml, Self::Json, Self::Toml];
+
+    /// File extension of the format e.g. `yaml`.
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Yaml => "yaml",
+            Self::Json => "json",
+            Self::Toml => "toml",
+        }
+    }
+
+    /// Parse a spec into a mapping.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `spec` isn't a valid document of this format or isn't a map.
+    pub fn parse(self, spec: &str) -> Result<Mapping> {
+        Ok(match self {
+            Self::Yaml => serde_yaml::from_str(spec)?,
+            Self::Json => serde_json::from_str(spec)?,
+            Self::Toml => toml::from_str(spec)?,
+        })
+    }
+
+    /// Write a spec mapping with `class` and `schema` at the top followed by the remaining keys in
+    /// sorted order.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the mapping has non-string keys or can't be represented in this
+    /// format.
+    pub fn render(self, mut spec_mapping: Mapping) -> Result<String> {
+        let mut ordered = Mapping::new();
+        for key in ["class", "schema"] {
+            if let Some(value) = spec_mapping.remove(key) {
+                ordered.insert(key.into(), value);
+            }
+        }
+        let fields: BTreeMap<String, Value> = serde_yaml::from_value(Value::Mapping(spec_mapping))?;
+        ordered.extend(fields.into_iter().map(|(key, value)| (key.into(), value)));
+
+        Ok(match self {
+            Self::Yaml => serde_yaml::to_string(&ordered)?,
+            Self::Json => serde_json::to_string_pretty(&ordered)? + "\n",
+            Self::Toml => toml::to_string(&without_nulls(Value::Mapping(ordered)))?,
+        })
+    }
+
+    /// Serialize a model into a value of this format's data model. YAML tags enum variants that
+    /// carry data while JSON and TOML represent them as single-key maps.
+    fn serialize_value<T: Serialize>(self, instance: &T) -> Result<Value> {
+        Ok(match self {
+            Self::Yaml => serde_yaml::to_value(instance)?,
+            Self::Json | Self::Toml => serde_yaml::to_value(serde_json::to_value(instance)?)?,
+        })
+    }
+
+    /// Deserialize a model from a value of this format's data model.
+    fn deserialize_value<T: DeserializeOwned>(self, value: Value) -> Result<T> {
+        Ok(match self {
+            Self::Yaml => serde_yaml::from_value(value)?,
+            Self::Json | Self::Toml => serde_json::from_value(serde_json::to_value(value)?)?,
+        })
+    }
+}
+
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .into_iter()
+                .filter(|(_, item)| !item.is_null())
+                .map(|(key, item)| (key, without_nulls(item)))
+                .collect(),
+        ),
+        Value::Sequence(sequence) => {
+            Value::Sequence(sequence.into_iter().map(without_nulls).collect())
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) | Value::Tagged(_) => {
+            value
+        }
+    }
+}
+
+/// Converts a model instance into a consistent spec of the given format.
+///
+/// The spec is headed by the model's `class` and `SCHEMA_VERSION` and leaves out the fields that
+/// don't affect the hash i.e. `annotation`, `hash` and `secret_env`.
+///
+/// # Errors
+///
+/// Will return `Err` if there is an issue converting an `instance` into `format` (w/o
+/// annotation).
+pub fn to_spec<T: Serialize>(instance: &T, format: SpecFormat) -> Result<String> {
+    let Value::Mapping(mut spec_mapping) = format.serialize_value(instance)? else {
+        return Err(OrcaError::from(Kind::CanonicalEncoding(format!(
+            "{} does not serialize to a map",
+            get_type_name::<T>()
+        ))));
+    };
+    for field in UNHASHED_FIELDS {
+        spec_mapping.remove(field);
+    }
+    spec_mapping.insert("class".into(), Value::from(get_type_name::<T>()));
+    spec_mapping.insert("schema".into(), Value::from(SCHEMA_VERSION));
+
+    format.render(spec_mapping)
+}
 /// Converts a model instance into a consistent yaml.
 ///
 /// # Errors
 ///
 /// Will return `Err` if there is an issue converting an `instance` into YAML (w/o annotation).
 pub fn to_yaml<T: Serialize>(instance: &T) -> Result<String> {
-    let mapping: BTreeMap<String, Value> = serde_yaml::from_str(&serde_yaml::to_string(instance)?)?; // sort
-    let mut yaml = serde_yaml::to_string(
-        &mapping
-            .into_iter()
-            .filter(|(k, _)| k != "annotation" && k != "hash")
-            .collect::<BTreeMap<_, _>>(),
-    )?; // skip fields
-    yaml.insert_str(0, &format!("class: {}\n", get_type_name::<T>())); // replace class at top
+    to_spec(instance, SpecFormat::Yaml)
+}
+/// Converts a model instance into a consistent JSON.
+///
+/// # Errors
+///
+/// Will return `Err` if there is an issue converting an `instance` into JSON (w/o annotation).
+pub fn to_json<T: Serialize>(instance: &T) -> Result<String> {
+    to_spec(instance, SpecFormat::Json)
+}
+/// Converts a model instance into a consistent TOML.
+///
+/// # Errors
+///
+/// Will return `Err` if there is an issue converting an `instance` into TOML (w/o annotation).
+pub fn to_toml<T: Serialize>(instance: &T) -> Result<String> {
+    to_spec(instance, SpecFormat::Toml)
+}
+/// Top-level fields that don't affect a model's hash.
+pub(crate) const UNHASHED_FIELDS: [&str; 3] = ["annotation", "hash", "secret_env"];
+/// Converts a model instance into the canonical text its hash is computed from.
+///
+/// The encoding is defined here rather than by any serialization library so that hashes stay
+/// stable across dependency upgrades:
+///
+/// - The model is encoded as a map without `annotation`, `hash` and `secret_env` (which secret
+///   store provides a value doesn't affect reproducibility) and with `class` set to the lowercase
+///   model type name.
+/// - Maps (including structs) are written as `{"key":value,...}` with keys sorted by their UTF-8
+///   bytes. Sequences are written as `[value,...]`. No whitespace is emitted.
+/// - Strings are double-quoted. `"` and `\` are backslash-escaped, U+0000 to U+001F are written
+///   as `\u00XX` (lowercase hex) and everything else is written as is in UTF-8.
+/// - Integers are written in decimal. Floats must be finite and are written as the shortest
+///   decimal that round-trips to the same value at the original precision without an exponent
+///   or trailing `.0`, e.g. `0.25` or `2`.
+/// - Booleans are `true`/`false`. `None`, unit and unit structs are `null`. `Some(value)` and
+///   newtype structs are written as the inner value.
+/// - Enum variants are externally tagged: unit variants as `"Variant"`, all others as
+///   `{"Variant":value}` where tuple variants become a sequence and struct variants a map.
+///
+/// # Errors
+///
+/// Will return `Err` if `instance` doesn't serialize to a map, contains non-finite floats or
+/// non-string map keys.
+pub fn to_canonical<T: Serialize>(instance: &T) -> Result<String> {
+    let Canonical::Map(mut mapping) = Canonical::from_serialize(instance)? else {
+        return Err(OrcaError::from(Kind::CanonicalEncoding(format!(
+            "{} does not serialize to a map",
+            get_type_name::<T>()
+        ))));
+    };
+    for field in UNHASHED_FIELDS {
+        mapping.remove(field);
+    }
+    mapping.insert("class".to_owned(), Canonical::String(get_type_name::<T>()));
 
-    Ok(yaml)
+    Ok(Canonical::Map(mapping).to_string())
+}
+/// Converts a spec as it was written into the canonical text its hash was computed from.
+///
+/// This is what the hash of a spec of an older schema version has to be checked against since
+/// the model it migrates into encodes differently. `model` supplies the `None` fields TOML leaves
+/// out.
+///
+/// # Errors
+///
+/// Will return `Err` if the spec contains non-finite floats or non-string map keys.
+pub(crate) fn spec_to_canonical<T: Serialize>(spec_mapping: &Mapping, model: &T) -> Result<String> {
+    let (Canonical::Map(mut mapping), Canonical::Map(model_mapping)) = (
+        Canonical::from_serialize(&untagged(Value::Mapping(spec_mapping.clone())))?,
+        Canonical::from_serialize(model)?,
+    ) else {
+        return Err(OrcaError::from(Kind::CanonicalEncoding(format!(
+            "{} does not serialize to a map",
+            get_type_name::<T>()
+        ))));
+    };
+    restore_nulls(&mut mapping, &model_mapping);
+    mapping.remove("schema");
+    for field in UNHASHED_FIELDS {
+        mapping.remove(field);
+    }
+    mapping.insert("class".to_owned(), Canonical::String(get_type_name::<T>()));
+
+    Ok(Canonical::Map(mapping).to_string())
+}
+
+/// Rewrite YAML-tagged enum variants e.g. `!NVIDIA A100` as the single-key maps they serialize
+/// to everywhere else.
+fn untagged(value: Value) -> Value {
+    match value {
+        Value::Tagged(tagged) => Value::Mapping(Mapping::from_iter([(
+            Value::from(tagged.tag.to_string().trim_start_matches('!')),
+            untagged(tagged.value),
+        )])),
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, item)| (key, untagged(item)))
+                .collect(),
+        ),
+        Value::Sequence(sequence) => Value::Sequence(sequence.into_iter().map(untagged).collect()),
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => value,
+    }
+}
+
+/// Put back the `None` fields of a model that a TOML spec leaves out.
+fn restore_nulls(
+    mapping: &mut BTreeMap<String, Canonical>,
+    model_mapping: &BTreeMap<String, Canonical>,
+) {
+    for (key, model_value) in model_mapping {
+        match (mapping.get_mut(key), model_value) {
+            (None, Canonical::Null) => {
+                mapping.insert(key.clone(), Canonical::Null);
+            }
+            (Some(Canonical::Map(value_mapping)), Canonical::Map(model_value_mapping)) => {
+                restore_nulls(value_mapping, model_value_mapping);
+            }
+            _ => {}
+        }
+    }
+}
+/// Instantiates a model from a spec of the given format, its unique hash and its YAML
+/// annotation. Specs of an older schema version are upgraded first.
+///
+/// # Errors
+///
+/// Will return `Err` if there is an issue converting the spec+annotation into a model instance.
+pub fn from_spec<T: DeserializeOwned>(
+    spec: &str,
+    format: SpecFormat,
+    hash: &str,
+    annotation_yaml: Option<&str>,
+) -> Result<T> {
+    from_spec_with_secrets(spec, format, hash, annotation_yaml, None)
+}
+/// Instantiates a model like `from_spec` along with the YAML secret references kept apart from
+/// its spec.
+pub(crate) fn from_spec_with_secrets<T: DeserializeOwned>(
+    spec: &str,
+    format: SpecFormat,
+    hash: &str,
+    annotation_yaml: Option<&str>,
+    secret_env_yaml: Option<&str>,
+) -> Result<T> {
+    let mut spec_mapping = format.parse(spec)?;
+    migrate::<T>(&mut spec_mapping, MIGRATIONS)?;
+
+    // Insert annotation and secret references if there is something
+    for (field, yaml) in [
+        ("annotation", annotation_yaml),
+        ("secret_env", secret_env_yaml),
+    ] {
+        if let Some(field_yaml) = yaml {
+            let field_map: Mapping = serde_yaml::from_str(field_yaml)?;
+            spec_mapping.insert(field.into(), Value::from(field_map));
+        }
+    }
+    spec_mapping.insert("hash".into(), Value::from(hash));
+
+    format.deserialize_value(Value::Mapping(spec_mapping))
 }
 /// Instantiates a model from from yaml content and its unique hash.
 ///
@@ -33,24 +323,119 @@ pub fn from_yaml<T: DeserializeOwned>(
     hash: &str,
     annotation_yaml: Option<&str>,
 ) -> Result<T> {
-    let mut spec_mapping: BTreeMap<String, Value> = serde_yaml::from_str(spec_yaml)?;
+    from_spec(spec_yaml, SpecFormat::Yaml, hash, annotation_yaml)
+}
+/// Instantiates a model from JSON content, its unique hash and its YAML annotation.
+///
+/// # Errors
+///
+/// Will return `Err` if there is an issue converting the JSON spec+YAML annotation into a model
+/// instance.
+pub fn from_json<T: DeserializeOwned>(
+    spec_json: &str,
+    hash: &str,
+    annotation_yaml: Option<&str>,
+) -> Result<T> {
+    from_spec(spec_json, SpecFormat::Json, hash, annotation_yaml)
+}
+/// Instantiates a model from TOML content, its unique hash and its YAML annotation.
+///
+/// # Errors
+///
+/// Will return `Err` if there is an issue converting the TOML spec+YAML annotation into a model
+/// instance.
+pub fn from_toml<T: DeserializeOwned>(
+    spec_toml: &str,
+    hash: &str,
+    annotation_yaml: Option<&str>,
+) -> Result<T> {
+    from_spec(spec_toml, SpecFormat::Toml, hash, annotation_yaml)
+}
+
+// --- core model structs ---
 
-    // Insert annotation if there is something
-    if let Some(yaml) = annotation_yaml {
-        let annotation_map: Mapping = serde_yaml::from_str(yaml)?;
-        spec_mapping.insert("annotation".into(), Value::from(annotation_map));
+/// Behavior shared by every model a store can hold.
+pub trait Model: Serialize + DeserializeOwned {
+    /// Lowercase type name models of this type are filed under e.g. `pod` or `podjob`.
+    fn class_name() -> String {
+        get_type_name::<Self>()
     }
-    spec_mapping.insert("hash".to_owned(), Value::from(hash));
 
-    Ok(serde_yaml::from_str(&serde_yaml::to_string(
-        &spec_mapping,
-    )?)?)
+    /// Unique id based on reproducibility.
+    fn hash(&self) -> &str;
+
+    /// Metadata the model can be looked up by name with, `None` if it can only be found by hash.
+    fn annotation(&self) -> Option<&Annotation>;
+
+    /// Image and source references that aren't pinned, which a store's pin policy is enforced
+    /// on. None by default.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a reference can't be parsed.
+    fn unpinned_references(&self) -> Result<Vec<OrcaError>> {
+        Ok(Vec::new())
+    }
 }
 
-// --- core model structs ---
+impl Model for Pod {
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn annotation(&self) -> Option<&Annotation> {
+        self.annotation.as_ref()
+    }
+
+    fn unpinned_references(&self) -> Result<Vec<OrcaError>> {
+        let image = self.image_reference()?;
+        let source = self.source_reference()?;
+        let mut unpinned = Vec::new();
+        if !image.is_pinned() {
+            unpinned.push(OrcaError::from(Kind::UnpinnedImage(image.to_string())));
+        }
+        if !source.is_pinned() {
+            unpinned.push(OrcaError::from(Kind::UnpinnedSource(
+                source.to_string(),
+                source.git_ref,
+            )));
+        }
+        Ok(unpinned)
+    }
+}
+
+impl Model for PodJob {
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn annotation(&self) -> Option<&Annotation> {
+        self.annotation.as_ref()
+    }
+}
+
+impl Model for PodResult {
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn annotation(&self) -> Option<&Annotation> {
+        self.annotation.as_ref()
+    }
+}
+
+impl Model for Pipeline {
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn annotation(&self) -> Option<&Annotation> {
+        self.annotation.as_ref()
+    }
+}
 
 /// A reusable, containerized computational unit.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Pod {
     /// Metadata that doesn't affect reproducibility.
     pub annotation: Option<Annotation>,
@@ -58,12 +443,16 @@ pub struct Pod {
     pub hash: String,
     source_commit_url: String,
     image: String,
-    command: String,
+    command: Command,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    secret_env: BTreeMap<String, SecretRef>,
     input_stream_map: BTreeMap<String, StreamInfo>,
     output_dir: PathBuf,
     output_stream_map: BTreeMap<String, StreamInfo>,
-    recommended_cpus: f32,
-    recommended_memory: u64,
+    recommended_cpus: Cpu,
+    recommended_memory: Memory,
     required_gpu: Option<GPURequirement>,
 }
 
@@ -72,75 +461,1249 @@ impl Pod {
     ///
     /// # Errors
     ///
-    /// Will return `Err` if there is an issue initializing a `Pod` instance.
+    /// Will return `Err` if any field fails validation (all problems are reported together) or if
+    /// there is an issue initializing a `Pod` instance.
+    #[expect(
+        clippy::too_many_arguments,
+        reason = "One argument per field, `Pod::builder` names them."
+    )]
     pub fn new(
         annotation: Option<Annotation>,
         source_commit_url: String,
         image: String,
-        command: String,
+        command: Command,
+        env: BTreeMap<String, String>,
+        secret_env: BTreeMap<String, SecretRef>,
         input_stream_map: BTreeMap<String, StreamInfo>,
         output_dir: PathBuf,
         output_stream_map: BTreeMap<String, StreamInfo>,
-        recommended_cpus: f32,
-        recommended_memory: u64,
+        recommended_cpus: Cpu,
+        recommended_memory: Memory,
         required_gpu: Option<GPURequirement>,
     ) -> Result<Self> {
-        let pod_no_hash = Self {
+        Self::hashed(
+            Self {
+                annotation,
+                hash: String::new(),
+                source_commit_url,
+                image,
+                command,
+                env,
+                secret_env,
+                input_stream_map,
+                output_dir,
+                output_stream_map,
+                recommended_cpus,
+                recommended_memory,
+                required_gpu,
+            },
+            HashAlgorithm::default(),
+        )
+    }
+
+    /// Validate `pod_no_hash` and hash it with `hash_algorithm`, the one place `Pod::new` and
+    /// `PodBuilder::build` create pods through.
+    fn hashed(pod_no_hash: Self, hash_algorithm: HashAlgorithm) -> Result<Self> {
+        let pod = Self {
+            // hash the canonical spelling so equivalent URLs give the same pod
+            source_commit_url: SourceReference::try_parse(&pod_no_hash.source_commit_url)
+                .map_or(pod_no_hash.source_commit_url, |source| source.to_string()),
+            ..pod_no_hash
+        };
+        pod.validate()?;
+        Ok(Self {
+            hash: hash_algorithm.digest(&to_canonical(&pod)?),
+            ..pod
+        })
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        for (field, value) in [
+            ("source_commit_url", &self.source_commit_url),
+            ("image", &self.image),
+        ] {
+            if value.trim().is_empty() {
+                problems.push(Kind::EmptyField(get_type_name::<Self>(), field.into()));
+            }
+        }
+        problems.extend(self.command_problems());
+        if !self.source_commit_url.trim().is_empty() {
+            if let Err(reason) = SourceReference::try_parse(&self.source_commit_url) {
+                problems.push(Kind::InvalidSourceReference(
+                    self.source_commit_url.clone(),
+                    reason.into(),
+                ));
+            }
+        }
+        if !self.image.trim().is_empty() {
+            if let Err(reason) = ImageReference::try_parse(&self.image) {
+                problems.push(Kind::InvalidImageReference(
+                    self.image.clone(),
+                    reason.into(),
+                ));
+            }
+        }
+
+        for name in self.env.keys().chain(self.secret_env.keys()) {
+            if !is_env_name(name) {
+                problems.push(Kind::InvalidEnvName(name.clone()));
+            }
+        }
+        for name in self.env.keys() {
+            if self.secret_env.contains_key(name) {
+                problems.push(Kind::ConflictingEnvName(name.clone()));
+            }
+        }
+        for secret in self.secret_env.values() {
+            if !secret.is_valid() {
+                problems.push(Kind::InvalidSecretName(secret.name.clone()));
+            }
+        }
+
+        if self.recommended_cpus == Cpu::ZERO {
+            problems.push(Kind::InvalidResource(
+                "recommended_cpus".into(),
+                self.recommended_cpus.to_string(),
+            ));
+        }
+        if self.recommended_memory == Memory::ZERO {
+            problems.push(Kind::InvalidResource(
+                "recommended_memory".into(),
+                self.recommended_memory.to_string(),
+            ));
+        }
+        if let Some(gpu) = &self.required_gpu {
+            if gpu.count == 0 {
+                problems.push(Kind::InvalidResource(
+                    "required_gpu.count".into(),
+                    gpu.count.to_string(),
+                ));
+            }
+        }
+
+        for (key, stream_info) in self.input_stream_map.iter().chain(&self.output_stream_map) {
+            if let Err(error) = glob::Pattern::new(&stream_info.match_pattern) {
+                problems.push(Kind::InvalidMatchPattern(key.clone(), error));
+            }
+            if let Some(media_type) = &stream_info.media_type {
+                if !is_media_type(media_type) {
+                    problems.push(Kind::InvalidMediaType(key.clone(), media_type.clone()));
+                }
+            }
+        }
+
+        // output streams must land inside `output_dir` and must not overwrite each other
+        let output_dir = normalize_path(&self.output_dir);
+        let mut output_paths = BTreeMap::<PathBuf, Vec<String>>::new();
+        for (key, stream_info) in &self.output_stream_map {
+            let path = normalize_path(&output_dir.join(&stream_info.path));
+            if path == output_dir || !path.starts_with(&output_dir) {
+                problems.push(Kind::StreamPathOutsideOutputDir(
+                    key.clone(),
+                    stream_info.path.clone(),
+                ));
+            }
+            output_paths.entry(path).or_default().push(key.clone());
+        }
+        for (path, keys) in output_paths {
+            if keys.len() > 1 {
+                problems.push(Kind::DuplicateOutputPath(path, keys));
+            }
+        }
+
+        report_problems(problems)
+    }
+
+    /// Environment the pod's container runs with, where secrets are resolved through
+    /// `provider`. Secret values are only ever held in the returned map.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `provider` fails to resolve any of the secrets (all missing secrets
+    /// are reported together).
+    pub fn resolve_env(&self, provider: &impl SecretProvider) -> Result<BTreeMap<String, String>> {
+        let mut env = self.env.clone();
+        let mut problems = Vec::new();
+        for (name, secret) in &self.secret_env {
+            match provider.get_secret(secret) {
+                Ok(value) => {
+                    env.insert(name.clone(), value);
+                }
+                Err(error) => problems.push(error),
+            }
+        }
+        match problems.len() {
+            0 => Ok(env),
+            1 => Err(problems.remove(0)),
+            _ => Err(OrcaError::from(Kind::ValidationErrors(problems))),
+        }
+    }
+
+    /// Forge, repository, ref and subpath of the source code the pod's image was built from.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the source isn't a supported reference, which only a pod deserialized
+    /// from a hand-edited spec can have.
+    pub fn source_reference(&self) -> Result<SourceReference> {
+        self.source_commit_url.parse()
+    }
+
+    /// Registry, repository, tag and digest of the container image the pod runs.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the image isn't a valid reference, which only a pod deserialized from
+    /// a hand-edited spec can have.
+    pub fn image_reference(&self) -> Result<ImageReference> {
+        self.image.parse()
+    }
+
+    /// An empty command or placeholders that don't refer to a stream of the pod.
+    fn command_problems(&self) -> Vec<Kind> {
+        let mut problems = Vec::new();
+        if self.command.is_empty() {
+            problems.push(Kind::EmptyField(get_type_name::<Self>(), "command".into()));
+        }
+        for template in self.command.templates() {
+            if let Err(reason) = expand_template(template, |placeholder| {
+                self.placeholder_path(placeholder).map(|_| String::new())
+            }) {
+                problems.push(Kind::InvalidCommandTemplate(template.clone(), reason));
+            }
+        }
+        problems
+    }
+
+    /// Program and arguments to execute in the pod's container with every placeholder replaced
+    /// by the container path it refers to. A shell command is run as `sh -c` with the paths
+    /// quoted for the shell.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a placeholder doesn't refer to a stream of the pod, which only a pod
+    /// deserialized from a hand-edited spec can have.
+    pub fn command_argv(&self) -> Result<Vec<String>> {
+        let expand = |template: &String, quote: fn(String) -> String| {
+            expand_template(template, |placeholder| {
+                self.placeholder_path(placeholder)
+                    .map(|path| quote(path.to_string_lossy().into_owned()))
+            })
+            .map_err(|reason| {
+                OrcaError::from(Kind::InvalidCommandTemplate(template.clone(), reason))
+            })
+        };
+        match &self.command {
+            Command::Shell(script) => Ok(vec![
+                "sh".to_owned(),
+                "-c".to_owned(),
+                expand(script, shell_quote)?,
+            ]),
+            Command::Argv(argv) => argv
+                .iter()
+                .map(|argument| expand(argument, |path| path))
+                .collect(),
+        }
+    }
+
+    /// Container path a command placeholder such as `inputs.painting` stands for.
+    fn placeholder_path(&self, placeholder: &str) -> result::Result<PathBuf, String> {
+        match placeholder.split_once('.') {
+            None if placeholder == "output_dir" => Ok(self.output_dir.clone()),
+            Some(("inputs", key)) => self
+                .input_stream_map
+                .get(key)
+                .map(|stream_info| stream_info.path.clone())
+                .ok_or_else(|| format!("pod has no input stream `{key}`")),
+            Some(("outputs", key)) => self
+                .output_stream_map
+                .get(key)
+                .map(|stream_info| normalize_path(&self.output_dir.join(&stream_info.path)))
+                .ok_or_else(|| format!("pod has no output stream `{key}`")),
+            _ => Err(format!(
+                "unknown placeholder `{{{placeholder}}}`, expected `{{inputs.<key>}}`, \
+                 `{{outputs.<key>}}` or `{{output_dir}}`"
+            )),
+        }
+    }
+
+    /// Start building a pod with named setters instead of positional arguments.
+    pub fn builder() -> PodBuilder {
+        PodBuilder::default()
+    }
+}
+
+/// Fluent alternative to `Pod::new`. Defaults to no GPU and empty stream maps.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[must_use]
+pub struct PodBuilder {
+    annotation: Option<Annotation>,
+    source_commit_url: Option<String>,
+    image: Option<String>,
+    command: Option<Command>,
+    env: BTreeMap<String, String>,
+    secret_env: BTreeMap<String, SecretRef>,
+    input_stream_map: BTreeMap<String, StreamInfo>,
+    output_dir: Option<PathBuf>,
+    output_stream_map: BTreeMap<String, StreamInfo>,
+    recommended_cpus: Option<Cpu>,
+    recommended_memory: Option<Memory>,
+    required_gpu: Option<GPURequirement>,
+    hash_algorithm: HashAlgorithm,
+}
+
+impl PodBuilder {
+    /// Set metadata that doesn't affect reproducibility.
+    pub fn annotation(mut self, annotation: Annotation) -> Self {
+        self.annotation = Some(annotation);
+        self
+    }
+
+    /// Set the URL to the source code commit the image was built from.
+    pub fn source_commit_url(mut self, source_commit_url: impl Into<String>) -> Self {
+        self.source_commit_url = Some(source_commit_url.into());
+        self
+    }
+
+    /// Set the container image to run.
+    pub fn image(mut self, image: impl Into<String>) -> Self {
+        self.image = Some(image.into());
+        self
+    }
+
+    /// Set the command to run inside the container, either a shell string or an argv vector.
+    pub fn command(mut self, command: impl Into<Command>) -> Self {
+        self.command = Some(command.into());
+        self
+    }
+
+    /// Set an environment variable, replacing any previous value.
+    pub fn env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set an environment variable to the value of a secret when the pod runs. The secret isn't
+    /// part of the pod's hash.
+    pub fn secret_env(mut self, name: impl Into<String>, secret: SecretRef) -> Self {
+        self.secret_env.insert(name.into(), secret);
+        self
+    }
+
+    /// Add a named input stream, replacing any previous stream with the same key.
+    pub fn input_stream(mut self, key: impl Into<String>, stream_info: StreamInfo) -> Self {
+        self.input_stream_map.insert(key.into(), stream_info);
+        self
+    }
+
+    /// Set the directory inside the container where outputs are written.
+    pub fn output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = Some(output_dir.into());
+        self
+    }
+
+    /// Add a named output stream, replacing any previous stream with the same key.
+    pub fn output_stream(mut self, key: impl Into<String>, stream_info: StreamInfo) -> Self {
+        self.output_stream_map.insert(key.into(), stream_info);
+        self
+    }
+
+    /// Set the recommended amount of CPU.
+    pub const fn recommended_cpus(mut self, recommended_cpus: Cpu) -> Self {
+        self.recommended_cpus = Some(recommended_cpus);
+        self
+    }
+
+    /// Set the recommended amount of memory.
+    pub const fn recommended_memory(mut self, recommended_memory: Memory) -> Self {
+        self.recommended_memory = Some(recommended_memory);
+        self
+    }
+
+    /// Require a GPU.
+    pub fn required_gpu(mut self, required_gpu: GPURequirement) -> Self {
+        self.required_gpu = Some(required_gpu);
+        self
+    }
+
+    /// Hash the pod with `hash_algorithm` instead of the default. Pod jobs, pod results and
+    /// pipelines made from the pod are hashed with the same algorithm.
+    pub const fn hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+
+    /// Validate the configuration and compute the hash of the resulting pod.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a required field was never set or if there is an issue initializing
+    /// a `Pod` instance.
+    pub fn build(self) -> Result<Pod> {
+        match (
+            self.source_commit_url,
+            self.image,
+            self.command,
+            self.output_dir,
+            self.recommended_cpus,
+            self.recommended_memory,
+        ) {
+            (
+                Some(source_commit_url),
+                Some(image),
+                Some(command),
+                Some(output_dir),
+                Some(recommended_cpus),
+                Some(recommended_memory),
+            ) => Pod::hashed(
+                Pod {
+                    annotation: self.annotation,
+                    hash: String::new(),
+                    source_commit_url,
+                    image,
+                    command,
+                    env: self.env,
+                    secret_env: self.secret_env,
+                    input_stream_map: self.input_stream_map,
+                    output_dir,
+                    output_stream_map: self.output_stream_map,
+                    recommended_cpus,
+                    recommended_memory,
+                    required_gpu: self.required_gpu,
+                },
+                self.hash_algorithm,
+            ),
+            (
+                source_commit_url,
+                image,
+                command,
+                output_dir,
+                recommended_cpus,
+                recommended_memory,
+            ) => Err(combine_problems(
+                [
+                    ("source_commit_url", source_commit_url.is_none()),
+                    ("image", image.is_none()),
+                    ("command", command.is_none()),
+                    ("output_dir", output_dir.is_none()),
+                    ("recommended_cpus", recommended_cpus.is_none()),
+                    ("recommended_memory", recommended_memory.is_none()),
+                ]
+                .into_iter()
+                .filter(|(_, missing)| *missing)
+                .map(|(field, _)| Kind::MissingField(get_type_name::<Pod>(), field.into()))
+                .collect(),
+            )),
+        }
+    }
+}
+
+/// POSIX portable environment variable names: `[A-Za-z_][A-Za-z0-9_]*`.
+fn is_env_name(name: &str) -> bool {
+    name.chars().enumerate().all(|(index, character)| {
+        character == '_'
+            || character.is_ascii_alphabetic()
+            || (index > 0 && character.is_ascii_digit())
+    }) && !name.is_empty()
+}
+
+/// Surface a single problem as is and several problems together, if there are any.
+fn report_problems(problems: Vec<Kind>) -> Result<()> {
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(combine_problems(problems))
+    }
+}
+
+/// Surface a single problem as is and several problems together.
+fn combine_problems(mut problems: Vec<Kind>) -> OrcaError {
+    if problems.len() == 1 {
+        OrcaError::from(problems.remove(0))
+    } else {
+        OrcaError::from(Kind::ValidationErrors(
+            problems.into_iter().map(OrcaError::from).collect(),
+        ))
+    }
+}
+
+/// What a pod's container runs.
+///
+/// Every argument may contain placeholders for where streams are inside the container:
+/// `{inputs.<key>}`, `{outputs.<key>}` and `{output_dir}`. Any other brace is kept as is so shell
+/// code such as `${HOME}` or `awk '{print $1}'` runs unchanged, and a placeholder is written
+/// literally by doubling its braces e.g. `{{output_dir}}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Command {
+    /// A single string run by `sh -c` e.g. `tail -f /dev/null`.
+    Shell(String),
+    /// Program and arguments run as is without a shell e.g. `[python, train.py, {output_dir}]`.
+    Argv(Vec<String>),
+}
+
+impl Command {
+    /// Whether there is nothing to run.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Shell(script) => script.trim().is_empty(),
+            Self::Argv(argv) => argv.first().is_none_or(|program| program.trim().is_empty()),
+        }
+    }
+
+    /// Every string that may contain placeholders.
+    fn templates(&self) -> &[String] {
+        match self {
+            Self::Shell(script) => slice::from_ref(script),
+            Self::Argv(argv) => argv,
+        }
+    }
+}
+
+impl From<&str> for Command {
+    fn from(script: &str) -> Self {
+        Self::Shell(script.to_owned())
+    }
+}
+
+impl From<String> for Command {
+    fn from(script: String) -> Self {
+        Self::Shell(script)
+    }
+}
+
+impl From<Vec<String>> for Command {
+    fn from(argv: Vec<String>) -> Self {
+        Self::Argv(argv)
+    }
+}
+
+impl<const N: usize> From<[&str; N]> for Command {
+    fn from(argv: [&str; N]) -> Self {
+        Self::Argv(argv.into_iter().map(str::to_owned).collect())
+    }
+}
+
+/// Replace every placeholder in `template` with what `resolve` gives for it and write the ones in
+/// doubled braces with single braces. Every other brace is left alone.
+fn expand_template(
+    template: &str,
+    mut resolve: impl FnMut(&str) -> result::Result<String, String>,
+) -> result::Result<String, String> {
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some((before, after_brace)) = rest.split_once('{') {
+        expanded.push_str(before);
+        let escaped = after_brace.strip_prefix('{').and_then(|inner| {
+            inner
+                .split_once("}}")
+                .filter(|(placeholder, _)| is_placeholder(placeholder))
+        });
+        if let Some((placeholder, after)) = escaped {
+            expanded.push('{');
+            expanded.push_str(placeholder);
+            expanded.push('}');
+            rest = after;
+        } else if let Some((placeholder, after)) = after_brace
+            .split_once('}')
+            .filter(|(placeholder, _)| is_placeholder(placeholder))
+        {
+            expanded.push_str(&resolve(placeholder)?);
+            rest = after;
+        } else {
+            expanded.push('{');
+            rest = after_brace;
+        }
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Whether `text` between braces is `inputs.<key>`, `outputs.<key>` or `output_dir`.
+fn is_placeholder(text: &str) -> bool {
+    text == "output_dir"
+        || text
+            .strip_prefix("inputs.")
+            .or_else(|| text.strip_prefix("outputs."))
+            .is_some_and(|key| {
+                !key.is_empty()
+                    && !key
+                        .chars()
+                        .any(|character| character == '{' || character.is_whitespace())
+            })
+}
+
+/// Quote `argument` for `sh` unless it only has characters the shell leaves alone.
+fn shell_quote(argument: String) -> String {
+    if !argument.is_empty()
+        && argument.chars().all(|character| {
+            character.is_ascii_alphanumeric() || matches!(character, '_' | '-' | '.' | '/' | ':')
+        })
+    {
+        argument
+    } else {
+        format!("'{}'", argument.replace('\'', r"'\''"))
+    }
+}
+
+/// A compute job that binds a pod to concrete inputs and resource limits.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PodJob {
+    /// Metadata that doesn't affect reproducibility.
+    pub annotation: Option<Annotation>,
+    /// Unique id based on reproducibility.
+    pub hash: String,
+    pod_hash: String,
+    input_stream_map: BTreeMap<String, Input>,
+    cpu_limit: Cpu,
+    memory_limit: Memory,
+}
+
+impl PodJob {
+    /// Construct a new pod job instance, hashed with the algorithm `pod` is hashed with.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `input_stream_map` does not bind exactly the (required) input streams
+    /// of `pod`, if a binding doesn't match its stream's cardinality or media type or if there is
+    /// an issue initializing a `PodJob` instance.
+    pub fn new(
+        annotation: Option<Annotation>,
+        pod: &Pod,
+        input_stream_map: BTreeMap<String, Input>,
+        cpu_limit: Cpu,
+        memory_limit: Memory,
+    ) -> Result<Self> {
+        check_streams(
+            "input_stream_map",
+            &pod.input_stream_map,
+            &input_stream_map
+                .iter()
+                .map(|(key, input)| {
+                    let blobs = match input {
+                        Input::Blob(blob) => vec![blob],
+                        Input::Collection(blobs) => blobs.iter().collect(),
+                    };
+                    (key.clone(), blobs)
+                })
+                .collect(),
+        )?;
+
+        let pod_job_no_hash = Self {
             annotation,
             hash: String::new(),
-            source_commit_url,
-            image,
-            command,
+            pod_hash: pod.hash.clone(),
             input_stream_map,
-            output_dir,
+            cpu_limit,
+            memory_limit,
+        };
+        let (hash_algorithm, _) = HashAlgorithm::split(&pod.hash)?;
+        Ok(Self {
+            hash: hash_algorithm.digest(&to_canonical(&pod_job_no_hash)?),
+            ..pod_job_no_hash
+        })
+    }
+}
+
+/// The recorded outcome of executing a pod job.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PodResult {
+    /// Metadata that doesn't affect reproducibility.
+    pub annotation: Option<Annotation>,
+    /// Unique id based on reproducibility.
+    pub hash: String,
+    pod_job_hash: String,
+    status: Status,
+    exit_code: Option<i32>,
+    start_time: u64,
+    end_time: u64,
+    output_stream_map: BTreeMap<String, Vec<Blob>>,
+}
+
+impl PodResult {
+    /// Construct a new pod result instance, hashed with the algorithm `pod_job` is hashed with.
+    /// `start_time` and `end_time` are in seconds since the UNIX epoch.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `pod_job` was not created from `pod`, if `end_time` is before
+    /// `start_time`, if `output_stream_map` has streams `pod` doesn't output or misses required
+    /// ones of a `Completed` run, if the collected blobs don't match their stream's cardinality or
+    /// media type or if there is an issue initializing a `PodResult` instance.
+    pub fn new(
+        annotation: Option<Annotation>,
+        pod: &Pod,
+        pod_job: &PodJob,
+        status: Status,
+        exit_code: Option<i32>,
+        start_time: u64,
+        end_time: u64,
+        output_stream_map: BTreeMap<String, Vec<Blob>>,
+    ) -> Result<Self> {
+        // `pod` may have been stored with another algorithm than the job was made with
+        let (job_pod_algorithm, job_pod_digest) = HashAlgorithm::split(&pod_job.pod_hash)?;
+        let (pod_algorithm, pod_digest) = HashAlgorithm::split(&pod.hash)?;
+        let job_pod_hash = format!("{job_pod_algorithm}:{job_pod_digest}");
+        let is_job_pod = if job_pod_algorithm == pod_algorithm {
+            job_pod_digest == pod_digest
+        } else {
+            job_pod_algorithm.digest(&to_canonical(pod)?) == job_pod_hash
+        };
+        if !is_job_pod {
+            return Err(OrcaError::from(Kind::JobPodMismatch(
+                pod_job.pod_hash.clone(),
+                pod.hash.clone(),
+            )));
+        }
+        if end_time < start_time {
+            return Err(OrcaError::from(Kind::InvalidTimeRange(
+                start_time, end_time,
+            )));
+        }
+        // Runs that didn't complete may have produced any of their outputs
+        let expected_streams = if status == Status::Completed {
+            pod.output_stream_map.clone()
+        } else {
+            pod.output_stream_map
+                .iter()
+                .map(|(key, stream_info)| {
+                    (
+                        key.clone(),
+                        StreamInfo {
+                            optional: true,
+                            ..stream_info.clone()
+                        },
+                    )
+                })
+                .collect()
+        };
+        check_streams(
+            "output_stream_map",
+            &expected_streams,
+            &output_stream_map
+                .iter()
+                .map(|(key, blobs)| (key.clone(), blobs.iter().collect()))
+                .collect(),
+        )?;
+
+        let pod_result_no_hash = Self {
+            annotation,
+            hash: String::new(),
+            pod_job_hash: pod_job.hash.clone(),
+            status,
+            exit_code,
+            start_time,
+            end_time,
             output_stream_map,
-            recommended_cpus,
-            recommended_memory,
-            required_gpu,
         };
+        let (hash_algorithm, _) = HashAlgorithm::split(&pod_job.hash)?;
         Ok(Self {
-            hash: hash(&to_yaml(&pod_no_hash)?),
-            ..pod_no_hash
+            hash: hash_algorithm.digest(&to_canonical(&pod_result_no_hash)?),
+            ..pod_result_no_hash
         })
     }
 }
 
+/// A directed acyclic graph of pods where outputs of one pod feed inputs of another.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    /// Metadata that doesn't affect reproducibility.
+    pub annotation: Option<Annotation>,
+    /// Unique id based on reproducibility.
+    pub hash: String,
+    nodes: BTreeMap<String, String>,
+    edges: BTreeSet<Edge>,
+    input_stream_map: BTreeMap<String, BTreeSet<StreamRef>>,
+    output_stream_map: BTreeMap<String, StreamRef>,
+}
+
+impl Pipeline {
+    /// Construct a new pipeline instance where `pods` maps node names to the pod each node runs.
+    /// The pipeline is hashed with the algorithm its pods are hashed with.
+    /// Every required node input must be fed by exactly one edge or pipeline input in
+    /// `input_stream_map` and optional ones by at most one.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a stream reference points to an unknown node or stream key, if an
+    /// edge connects streams of mismatched media type, cardinality or optionality, if a node
+    /// input is left unconnected or connected more than once, if the graph contains a cycle or if
+    /// there is an issue initializing a `Pipeline` instance e.g. its pods are hashed with different
+    /// algorithms.
+    pub fn new(
+        annotation: Option<Annotation>,
+        pods: &BTreeMap<String, Pod>,
+        edges: Vec<Edge>,
+        input_stream_map: BTreeMap<String, BTreeSet<StreamRef>>,
+        output_stream_map: BTreeMap<String, StreamRef>,
+    ) -> Result<Self> {
+        let edge_set = edges.into_iter().collect::<BTreeSet<_>>();
+
+        // every reference must point to an existing stream on an existing node and edges must
+        // connect streams that agree on what they carry
+        let mut problems = Vec::new();
+        for edge in &edge_set {
+            let source = edge.source.resolve(pods, |pod| &pod.output_stream_map)?;
+            let target = edge.target.resolve(pods, |pod| &pod.input_stream_map)?;
+            if let Some(reason) = source.feed_problem(target) {
+                problems.push(Kind::IncompatibleStreams(
+                    edge.source.to_string(),
+                    edge.target.to_string(),
+                    reason,
+                ));
+            }
+        }
+        for stream_ref in input_stream_map.values().flatten() {
+            stream_ref.resolve(pods, |pod| &pod.input_stream_map)?;
+        }
+        for stream_ref in output_stream_map.values() {
+            stream_ref.resolve(pods, |pod| &pod.output_stream_map)?;
+        }
+        report_problems(problems)?;
+
+        // every required node input must be fed exactly once, optional ones at most once
+        let mut feed_count = BTreeMap::<&StreamRef, usize>::new();
+        for stream_ref in edge_set
+            .iter()
+            .map(|edge| &edge.target)
+            .chain(input_stream_map.values().flatten())
+        {
+            *feed_count.entry(stream_ref).or_default() += 1;
+        }
+        for (node, pod) in pods {
+            for (stream, stream_info) in &pod.input_stream_map {
+                let stream_ref = StreamRef {
+                    node: node.clone(),
+                    stream: stream.clone(),
+                };
+                match feed_count.get(&stream_ref) {
+                    None if stream_info.optional => {}
+                    None => {
+                        return Err(OrcaError::from(Kind::UnconnectedInput(
+                            stream_ref.node,
+                            stream_ref.stream,
+                        )))
+                    }
+                    Some(1) => {}
+                    Some(_) => {
+                        return Err(OrcaError::from(Kind::MultiplyConnectedInput(
+                            stream_ref.node,
+                            stream_ref.stream,
+                        )))
+                    }
+                }
+            }
+        }
+
+        check_acyclic(pods, &edge_set)?;
+
+        let pipeline_no_hash = Self {
+            annotation,
+            hash: String::new(),
+            nodes: pods
+                .iter()
+                .map(|(node, pod)| (node.clone(), pod.hash.clone()))
+                .collect(),
+            edges: edge_set,
+            input_stream_map,
+            output_stream_map,
+        };
+        let mut hash_algorithms = pods
+            .values()
+            .map(|pod| Ok(HashAlgorithm::split(&pod.hash)?.0))
+            .collect::<Result<BTreeSet<_>>>()?;
+        if hash_algorithms.len() > 1 {
+            return Err(OrcaError::from(Kind::MixedHashAlgorithms(
+                hash_algorithms.iter().map(ToString::to_string).collect(),
+            )));
+        }
+        let hash_algorithm = hash_algorithms.pop_first().unwrap_or_default();
+        Ok(Self {
+            hash: hash_algorithm.digest(&to_canonical(&pipeline_no_hash)?),
+            ..pipeline_no_hash
+        })
+    }
+}
+
+/// Kahn's algorithm, whatever can't be visited is part of (or downstream of) a cycle.
+fn check_acyclic(pods: &BTreeMap<String, Pod>, edges: &BTreeSet<Edge>) -> Result<()> {
+    let mut in_degree = pods
+        .keys()
+        .map(|node| (node.as_str(), 0_usize))
+        .collect::<BTreeMap<_, _>>();
+    let mut successors = BTreeMap::<&str, Vec<&str>>::new();
+    for edge in edges {
+        *in_degree.entry(&edge.target.node).or_default() += 1;
+        successors
+            .entry(&edge.source.node)
+            .or_default()
+            .push(&edge.target.node);
+    }
+
+    let mut ready = in_degree
+        .iter()
+        .filter(|(_, degree)| **degree == 0)
+        .map(|(node, _)| *node)
+        .collect::<Vec<_>>();
+    while let Some(node) = ready.pop() {
+        in_degree.remove(node);
+        for successor in successors.get(node).into_iter().flatten() {
+            if let Some(degree) = in_degree.get_mut(successor) {
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.push(successor);
+                }
+            }
+        }
+    }
+
+    if in_degree.is_empty() {
+        Ok(())
+    } else {
+        Err(OrcaError::from(Kind::PipelineCycle(
+            in_degree.into_keys().map(str::to_owned).collect(),
+        )))
+    }
+}
+
+/// Check blobs bound to a pod's streams. Every required stream must be bound, no unknown ones
+/// may be and each binding must match its stream's cardinality and media type.
+fn check_streams(
+    map_name: &str,
+    expected: &BTreeMap<String, StreamInfo>,
+    actual: &BTreeMap<String, Vec<&Blob>>,
+) -> Result<()> {
+    let mut problems = Vec::new();
+    let missing = expected
+        .iter()
+        .filter(|(key, stream_info)| !stream_info.optional && !actual.contains_key(*key))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    let unexpected = actual
+        .keys()
+        .filter(|key| !expected.contains_key(*key))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !missing.is_empty() || !unexpected.is_empty() {
+        problems.push(Kind::StreamKeyMismatch(
+            map_name.to_owned(),
+            missing,
+            unexpected,
+        ));
+    }
+    for (key, blobs) in actual {
+        if let Some(stream_info) = expected.get(key) {
+            stream_info.check_blobs(key, blobs, &mut problems);
+        }
+    }
+    report_problems(problems)
+}
+
 // --- util types ---
 
+/// Algorithm used to derive a model's hash from its canonical encoding.
+///
+/// Hashes are self-describing as `{algorithm}:{hex digest}` e.g. `sha256:13d6...`. A bare hex
+/// digest is read as SHA-256 since that is what was used before hashes were prefixed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum HashAlgorithm {
+    /// SHA-256, the default.
+    #[default]
+    Sha256,
+    /// BLAKE3 with the default 256-bit output.
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Name used as the prefix of hashes produced by this algorithm.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    /// Hash `buffer` into a prefixed hash.
+    pub fn digest(self, buffer: &str) -> String {
+        let digest = match self {
+            Self::Sha256 => format!("{:x}", Sha256::digest(buffer)),
+            Self::Blake3 => blake3::hash(buffer.as_bytes()).to_hex().to_string(),
+        };
+        format!("{}:{digest}", self.name())
+    }
+
+    /// Split a prefixed or bare hash into its algorithm and hex digest.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the prefix names an unsupported algorithm.
+    pub fn split(hash: &str) -> Result<(Self, &str)> {
+        match hash.split_once(':') {
+            Some((name, digest)) => Ok((name.parse()?, digest)),
+            None => Ok((Self::Sha256, hash)),
+        }
+    }
+}
+
+impl Display for HashAlgorithm {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = OrcaError;
+
+    fn from_str(name: &str) -> Result<Self> {
+        [Self::Sha256, Self::Blake3]
+            .into_iter()
+            .find(|algorithm| algorithm.name() == name)
+            .ok_or_else(|| OrcaError::from(Kind::UnsupportedHashAlgorithm(name.to_owned())))
+    }
+}
+
 /// Standard metadata structure for all model instances.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Annotation {
     /// A unique name.
     pub name: String,
     /// A unique semantic version.
-    pub version: String,
+    pub version: Version,
     /// A long form description.
     pub description: String,
-}
-/// Specification for GPU requirements in computation.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct GPURequirement {
-    /// GPU model specification.
-    pub model: GPUModel,
-    /// Manufacturer recommended memory.
-    pub recommended_memory: u64,
-    /// Number of GPU cards required.
-    pub count: u16,
-}
-/// GPU model specification.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub enum GPUModel {
-    /// NVIDIA-manufactured card where `String` is the specific model e.g. ???
-    NVIDIA(String),
-    /// AMD-manufactured card where `String` is the specific model e.g. ???
-    AMD(String),
+    /// Free-form labels to find models by e.g. `team: vision` or `stage: prod`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// People responsible for the model e.g. `Jane Doe <jane@example.com>`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    /// License the model is shared under, ideally an SPDX identifier e.g. `MIT`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// When the model was created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
 }
 /// Streams are named and represent an abstration for the file(s) that represent some particular
 /// data.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct StreamInfo {
     /// Path to stream file.
     pub path: PathBuf,
     /// Naming pattern for the stream.
     pub match_pattern: String,
+    /// Media type of every blob in the stream e.g. `image/png`, `text/*` or `inode/directory` for
+    /// directories. Anything goes if `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    /// How many blobs the stream carries.
+    #[serde(default, skip_serializing_if = "Cardinality::is_default")]
+    pub cardinality: Cardinality,
+    /// Whether an input may be left unbound or an output may not be produced.
+    #[serde(default, skip_serializing_if = "ops::Not::not")]
+    pub optional: bool,
+}
+
+impl StreamInfo {
+    /// Collect problems with the blobs bound to stream `key`.
+    fn check_blobs(&self, key: &str, blobs: &[&Blob], problems: &mut Vec<Kind>) {
+        if !self.cardinality.accepts(blobs.len()) {
+            problems.push(Kind::StreamCardinalityMismatch(
+                key.to_owned(),
+                self.cardinality.to_string(),
+                blobs.len(),
+            ));
+        }
+        if let Some(media_type) = &self.media_type {
+            let expected_kind = if media_type == DIRECTORY_MEDIA_TYPE {
+                BlobKind::Directory
+            } else {
+                BlobKind::File
+            };
+            if let Some(blob) = blobs.iter().find(|blob| blob.kind != expected_kind) {
+                problems.push(Kind::StreamBlobKindMismatch(
+                    key.to_owned(),
+                    media_type.clone(),
+                    blob.location.clone(),
+                ));
+            }
+        }
+    }
+
+    /// Why data produced by this (output) stream can't be consumed by the `target` (input)
+    /// stream, if it can't.
+    fn feed_problem(&self, target: &Self) -> Option<String> {
+        if let (Some(source_type), Some(target_type)) = (&self.media_type, &target.media_type) {
+            if !media_types_match(source_type, target_type) {
+                return Some(format!(
+                    "media type `{source_type}` doesn't match `{target_type}`"
+                ));
+            }
+        }
+        if !self.cardinality.fits(target.cardinality) {
+            return Some(format!(
+                "{} blob(s) produced where {} accepted",
+                self.cardinality, target.cardinality
+            ));
+        }
+        if self.optional && !target.optional {
+            return Some("an optional output feeds a required input".to_owned());
+        }
+        None
+    }
+}
+
+/// Media type for streams of directories rather than files.
+const DIRECTORY_MEDIA_TYPE: &str = "inode/directory";
+
+/// `type/subtype` made of the characters RFC 6838 allows, where `*` is a wildcard.
+fn is_media_type(media_type: &str) -> bool {
+    let is_part = |part: &str| {
+        !part.is_empty()
+            && part.chars().all(|character| {
+                character.is_ascii_alphanumeric() || "!#$&-^_.+*".contains(character)
+            })
+    };
+    media_type
+        .split_once('/')
+        .is_some_and(|(top, sub)| is_part(top) && is_part(sub))
+}
+
+/// Whether two media types can describe the same data, with `*` matching any (sub)type.
+fn media_types_match(first: &str, second: &str) -> bool {
+    let part_matches = |left: &str, right: &str| left == "*" || right == "*" || left == right;
+    match (first.split_once('/'), second.split_once('/')) {
+        (Some((first_top, first_sub)), Some((second_top, second_sub))) => {
+            part_matches(first_top, second_top) && part_matches(first_sub, second_sub)
+        }
+        _ => first == second,
+    }
+}
+/// Number of blobs a stream carries.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cardinality {
+    /// A single blob.
+    One,
+    /// At least one blob.
+    OneOrMore,
+    /// Any number of blobs, including none.
+    #[default]
+    ZeroOrMore,
+}
+
+impl Cardinality {
+    /// Whether a stream of this cardinality may carry `count` blobs.
+    pub const fn accepts(self, count: usize) -> bool {
+        match self {
+            Self::One => count == 1,
+            Self::OneOrMore => count >= 1,
+            Self::ZeroOrMore => true,
+        }
+    }
+
+    /// Whether every count this cardinality allows is accepted by `target`.
+    pub const fn fits(self, target: Self) -> bool {
+        matches!(
+            (self, target),
+            (Self::One, _)
+                | (Self::OneOrMore, Self::OneOrMore | Self::ZeroOrMore)
+                | (Self::ZeroOrMore, Self::ZeroOrMore)
+        )
+    }
+
+    #[expect(
+        clippy::trivially_copy_pass_by_ref,
+        reason = "Signature required by `skip_serializing_if`."
+    )]
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Display for Cardinality {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::One => "exactly one",
+            Self::OneOrMore => "one or more",
+            Self::ZeroOrMore => "zero or more",
+        })
+    }
+}
+/// Final state of an executed pod job.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// Ran to completion successfully.
+    Completed,
+    /// Exited on its own with an error.
+    Failed,
+    /// Terminated externally before it could finish.
+    Killed,
+}
+/// Data bound to a stream, either a single blob or a collection of them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// A single file or directory.
+    Blob(Blob),
+    /// Many files or directories that together make up the stream.
+    Collection(Vec<Blob>),
+}
+/// A concrete file or directory on the host.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Blob {
+    /// Whether `location` points to a file or a directory.
+    pub kind: BlobKind,
+    /// Path to the blob on the host.
+    pub location: PathBuf,
+    /// Content hash of the blob.
+    pub checksum: String,
+}
+/// File system type of a blob.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum BlobKind {
+    /// A single file.
+    File,
+    /// A directory and everything in it.
+    Directory,
+}
+/// Reference to a stream of a specific node in a pipeline.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct StreamRef {
+    /// Name of the node in the pipeline.
+    pub node: String,
+    /// Key of the stream in the node's pod.
+    pub stream: String,
+}
+
+impl StreamRef {
+    fn resolve<'pods>(
+        &self,
+        pods: &'pods BTreeMap<String, Pod>,
+        stream_map: impl Fn(&Pod) -> &BTreeMap<String, StreamInfo>,
+    ) -> Result<&'pods StreamInfo> {
+        let pod = pods
+            .get(&self.node)
+            .ok_or_else(|| OrcaError::from(Kind::UnknownPipelineNode(self.node.clone())))?;
+        stream_map(pod).get(&self.stream).ok_or_else(|| {
+            OrcaError::from(Kind::DanglingStreamKey(
+                self.node.clone(),
+                self.stream.clone(),
+            ))
+        })
+    }
+}
+
+impl Display for StreamRef {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.node, self.stream)
+    }
+}
+/// Connection from an output stream of one node to an input stream of another.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Edge {
+    /// Output stream that produces the data.
+    pub source: StreamRef,
+    /// Input stream that consumes the data.
+    pub target: StreamRef,
 }
@@ -0,0 +1,279 @@
+use crate::error::{Kind, OrcaError, Result};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt::{self, Display, Formatter},
+    iter::Sum,
+    ops::{Add, Sub},
+    result,
+    str::FromStr,
+};
+
+/// Amount of CPU in millicores, written like Kubernetes quantities e.g. `250m` or `2`.
+///
+/// Whole cores are written without a suffix and anything else in millicores so every amount has
+/// exactly one spelling. Fractional cores such as `0.25` are accepted as long as they are a whole
+/// number of millicores. Plain numbers from older specs are rounded to the nearest millicore.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cpu(u64);
+
+impl Cpu {
+    /// No CPU at all.
+    pub const ZERO: Self = Self(0);
+
+    /// Amount of `millicores` thousandths of a core.
+    pub const fn from_millicores(millicores: u64) -> Self {
+        Self(millicores)
+    }
+
+    /// Amount of whole `cores`, saturating at the largest amount representable.
+    pub const fn from_cores(cores: u64) -> Self {
+        Self(cores.saturating_mul(1000))
+    }
+
+    /// Amount in thousandths of a core.
+    pub const fn as_millicores(self) -> u64 {
+        self.0
+    }
+
+    /// Amount in (fractional) cores.
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "Exact up to 2^52 millicores, far beyond any machine."
+    )]
+    pub fn as_cores(self) -> f64 {
+        self.0 as f64 / 1000.0
+    }
+
+    /// Subtract `other`, `None` if it is more than is available.
+    pub const fn checked_sub(self, other: Self) -> Option<Self> {
+        match self.0.checked_sub(other.0) {
+            Some(millicores) => Some(Self(millicores)),
+            None => None,
+        }
+    }
+}
+
+impl FromStr for Cpu {
+    type Err = OrcaError;
+
+    fn from_str(quantity: &str) -> Result<Self> {
+        quantity
+            .strip_suffix('m')
+            .map_or_else(
+                || parse_decimal(quantity, 1000),
+                |number| parse_decimal(number, 1),
+            )
+            .map(Self)
+            .ok_or_else(|| OrcaError::from(Kind::InvalidQuantity("CPU".into(), quantity.into())))
+    }
+}
+
+impl Display for Cpu {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.0.rem_euclid(1000) == 0 {
+            write!(f, "{}", self.0.div_euclid(1000))
+        } else {
+            write!(f, "{}m", self.0)
+        }
+    }
+}
+
+/// Saturates at the largest amount representable.
+impl Add for Cpu {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+/// Saturates at zero, use `checked_sub` to tell when more is taken than is available.
+impl Sub for Cpu {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Sum for Cpu {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl Serialize for Cpu {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cpu {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> result::Result<Self, D::Error> {
+        match RawQuantity::deserialize(deserializer)? {
+            RawQuantity::Text(quantity) => quantity.parse().map_err(D::Error::custom),
+            RawQuantity::Integer(cores) => cores
+                .checked_mul(1000)
+                .map(Self)
+                .ok_or_else(|| D::Error::custom(format!("CPU quantity `{cores}` is too large"))),
+            #[expect(
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss,
+                reason = "Finite and non-negative, anything beyond `u64::MAX` saturates."
+            )]
+            RawQuantity::Float(cores) if cores.is_finite() && cores >= 0.0 => {
+                Ok(Self((cores * 1000.0).round() as u64))
+            }
+            RawQuantity::Float(cores) => Err(D::Error::custom(format!(
+                "CPU quantity `{cores}` must be a finite number of at least zero"
+            ))),
+        }
+    }
+}
+
+/// Amount of memory in bytes, written like Kubernetes quantities e.g. `512Mi`, `2Gi` or `1G`.
+///
+/// Amounts are written with the largest binary suffix (`Ki`, `Mi`, `Gi`, `Ti`, `Pi`, `Ei`) that
+/// divides them exactly, falling back to the decimal ones (`k`, `M`, `G`, `T`, `P`, `E`) and then
+/// to plain bytes so every amount has exactly one spelling. Plain numbers are read as bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Memory(u64);
+
+const MEMORY_SUFFIXES: [(&str, u64); 12] = [
+    ("Ei", 1 << 60),
+    ("Pi", 1 << 50),
+    ("Ti", 1 << 40),
+    ("Gi", 1 << 30),
+    ("Mi", 1 << 20),
+    ("Ki", 1 << 10),
+    ("E", 1_000_000_000_000_000_000),
+    ("P", 1_000_000_000_000_000),
+    ("T", 1_000_000_000_000),
+    ("G", 1_000_000_000),
+    ("M", 1_000_000),
+    ("k", 1_000),
+];
+
+impl Memory {
+    /// No memory at all.
+    pub const ZERO: Self = Self(0);
+
+    /// Amount of `bytes`.
+    pub const fn from_bytes(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    /// Amount in bytes.
+    pub const fn as_bytes(self) -> u64 {
+        self.0
+    }
+
+    /// Subtract `other`, `None` if it is more than is available.
+    pub const fn checked_sub(self, other: Self) -> Option<Self> {
+        match self.0.checked_sub(other.0) {
+            Some(bytes) => Some(Self(bytes)),
+            None => None,
+        }
+    }
+}
+
+impl FromStr for Memory {
+    type Err = OrcaError;
+
+    fn from_str(quantity: &str) -> Result<Self> {
+        MEMORY_SUFFIXES
+            .iter()
+            .find_map(|(suffix, multiplier)| {
+                quantity
+                    .strip_suffix(suffix)
+                    .map(|number| parse_decimal(number, *multiplier))
+            })
+            .unwrap_or_else(|| parse_decimal(quantity, 1))
+            .map(Self)
+            .ok_or_else(|| OrcaError::from(Kind::InvalidQuantity("memory".into(), quantity.into())))
+    }
+}
+
+impl Display for Memory {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match MEMORY_SUFFIXES
+            .iter()
+            .find(|(_, multiplier)| self.0 >= *multiplier && self.0.rem_euclid(*multiplier) == 0)
+        {
+            Some((suffix, multiplier)) => write!(f, "{}{suffix}", self.0.div_euclid(*multiplier)),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// Saturates at the largest amount representable.
+impl Add for Memory {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+/// Saturates at zero, use `checked_sub` to tell when more is taken than is available.
+impl Sub for Memory {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Sum for Memory {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl Serialize for Memory {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Memory {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> result::Result<Self, D::Error> {
+        match RawQuantity::deserialize(deserializer)? {
+            RawQuantity::Text(quantity) => quantity.parse().map_err(D::Error::custom),
+            RawQuantity::Integer(bytes) => Ok(Self(bytes)),
+            RawQuantity::Float(bytes) => Err(D::Error::custom(format!(
+                "memory quantity `{bytes}` must be a whole number of bytes"
+            ))),
+        }
+    }
+}
+
+/// A quantity as written in a spec, either as a string or a plain number from older specs.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawQuantity {
+    Text(String),
+    Integer(u64),
+    Float(f64),
+}
+
+/// Parse a non-negative decimal like `1.5` into a whole number of units where one is
+/// `multiplier` units, `None` if it is malformed, not whole or too large.
+fn parse_decimal(number: &str, multiplier: u64) -> Option<u64> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, "0"));
+    let is_digits =
+        |part: &str| !part.is_empty() && part.chars().all(|character| character.is_ascii_digit());
+    if !is_digits(whole) || !is_digits(fraction) {
+        return None;
+    }
+
+    let scale = 10_u128.checked_pow(u32::try_from(fraction.len()).ok()?)?;
+    let numerator = format!("{whole}{fraction}")
+        .parse::<u128>()
+        .ok()?
+        .checked_mul(u128::from(multiplier))?;
+    if numerator.rem_euclid(scale) != 0 {
+        return None;
+    }
+    u64::try_from(numerator.div_euclid(scale)).ok()
+}
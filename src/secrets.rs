@@ -0,0 +1,127 @@
+use crate::error::{Kind, OrcaError, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Reference to a secret by name. Only the name is ever part of a model, the value is looked up
+/// from a `SecretProvider` when the pod is run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct SecretRef {
+    /// Name the secret is known by to its provider e.g. `hf-token`.
+    pub name: String,
+}
+
+impl SecretRef {
+    /// Reference the secret called `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+
+    /// Whether the name is safe to use as a file name or environment variable suffix i.e. it is
+    /// made of ASCII letters, digits, `-`, `_` and `.` and doesn't start with `.`.
+    pub fn is_valid(&self) -> bool {
+        !self.name.is_empty()
+            && !self.name.starts_with('.')
+            && self
+                .name
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || "-_.".contains(character))
+    }
+}
+
+/// Source of secret values on the machine running a pod.
+pub trait SecretProvider {
+    /// Look up the value of a secret.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the provider has no value for `secret` or reading it fails.
+    fn get_secret(&self, secret: &SecretRef) -> Result<String>;
+}
+
+/// Provides secrets from environment variables named by `prefix` followed by the secret name in
+/// upper case with `-` and `.` replaced by `_`, e.g. `ORCA_SECRET_HF_TOKEN` for `hf-token`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvSecretProvider {
+    prefix: String,
+}
+
+impl EnvSecretProvider {
+    /// Read secrets from environment variables starting with `prefix`.
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Name of the environment variable holding `secret`.
+    pub fn variable_name(&self, secret: &SecretRef) -> String {
+        format!(
+            "{}{}",
+            self.prefix,
+            secret.name.to_ascii_uppercase().replace(['-', '.'], "_")
+        )
+    }
+}
+
+impl SecretProvider for EnvSecretProvider {
+    fn get_secret(&self, secret: &SecretRef) -> Result<String> {
+        let variable_name = self.variable_name(secret);
+        match env::var(&variable_name) {
+            Ok(value) => Ok(value),
+            Err(env::VarError::NotPresent) => Err(OrcaError::from(Kind::SecretNotFound(
+                secret.name.clone(),
+                format!("environment variable `{variable_name}`"),
+            ))),
+            Err(env::VarError::NotUnicode(_)) => Err(OrcaError::from(Kind::SecretNotFound(
+                secret.name.clone(),
+                format!("environment variable `{variable_name}` as valid unicode"),
+            ))),
+        }
+    }
+}
+
+/// Provides secrets from files named after the secret in a directory, the way container
+/// orchestrators mount them. A single trailing newline is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSecretProvider {
+    directory: PathBuf,
+}
+
+impl FileSecretProvider {
+    /// Read secrets from files in `directory`.
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        Self {
+            directory: directory.as_ref().into(),
+        }
+    }
+}
+
+impl SecretProvider for FileSecretProvider {
+    fn get_secret(&self, secret: &SecretRef) -> Result<String> {
+        if !secret.is_valid() {
+            return Err(OrcaError::from(Kind::InvalidSecretName(
+                secret.name.clone(),
+            )));
+        }
+        let path = self.directory.join(&secret.name);
+        match fs::read_to_string(&path) {
+            Ok(value) => Ok(value
+                .strip_suffix('\n')
+                .map_or(value.as_str(), |stripped| {
+                    stripped.strip_suffix('\r').unwrap_or(stripped)
+                })
+                .to_owned()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                Err(OrcaError::from(Kind::SecretNotFound(
+                    secret.name.clone(),
+                    format!("file `{}`", path.to_string_lossy()),
+                )))
+            }
+            Err(error) => Err(OrcaError::from(error)),
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use crate::error::{Kind, OrcaError, Result};
+use std::{
+    fmt::{self, Display, Formatter},
+    result,
+    str::FromStr,
+};
+
+/// Code host a source reference points into, which decides how its URL is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Forge {
+    /// `https://<host>/<owner>/<repo>/tree/<ref>/<subpath>` (also `blob` and `commit`).
+    GitHub,
+    /// `https://<host>/<group>/…/<repo>/-/tree/<ref>/<subpath>` (also `blob` and `commit`).
+    GitLab,
+    /// `https://bitbucket.org/<workspace>/<repo>/src/<ref>/<subpath>` (also `commits`).
+    Bitbucket,
+    /// `file://<absolute path>@<ref>#<subpath>` for a git repository on the local filesystem,
+    /// where the ref and subpath are optional.
+    Local,
+}
+
+/// A pod's source code location split into its parts e.g.
+/// `https://github.com/zenml-io/zenml/tree/0.67.0/src/zenml`.
+///
+/// Different spellings of the same location (`http` vs `https`, a `www.` or `.git` suffix,
+/// `blob` or `commit` vs `tree`, trailing slashes, upper case commit SHAs, scp-style remotes
+/// such as `git@github.com:zenml-io/zenml.git@0.67.0`) parse to the same reference and are
+/// written back in one canonical form. A ref containing `/` can't be told apart from a subpath
+/// so its first segment is taken as the ref. Remotes and local paths may leave out the ref
+/// (`@<ref>`) to follow whatever is checked out, which is never pinned.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceReference {
+    /// Code host layout.
+    pub forge: Forge,
+    /// Lowercase host with an optional port, `None` for `Local`.
+    pub host: Option<String>,
+    /// Repository path on the host e.g. `zenml-io/zenml` or the repository's directory for
+    /// `Local`.
+    pub repository: String,
+    /// Commit SHA, tag or branch checked out, `None` if the reference doesn't name one.
+    pub git_ref: Option<String>,
+    /// Directory or file within the repository.
+    pub subpath: Option<String>,
+}
+
+impl SourceReference {
+    /// Whether `git_ref` is a full (SHA-1 or SHA-256) commit SHA, which can't be moved the way
+    /// tags and branches can.
+    pub fn is_pinned(&self) -> bool {
+        self.git_ref.as_deref().is_some_and(is_commit_sha)
+    }
+
+    /// Parse `source`, explaining what is wrong with it if it isn't a supported reference.
+    pub(crate) fn try_parse(source: &str) -> result::Result<Self, &'static str> {
+        let trimmed = source.trim();
+        if let Some(path) = trimmed.strip_prefix("file://") {
+            return Self::parse_local(path);
+        }
+        if trimmed.starts_with('/') {
+            return Self::parse_local(trimmed);
+        }
+
+        let Some(url) = trimmed
+            .strip_prefix("https://")
+            .or_else(|| trimmed.strip_prefix("http://"))
+        else {
+            return match trimmed.split_once(':') {
+                Some((user_host, location))
+                    if !user_host.contains('/') && !location.starts_with("//") =>
+                {
+                    Self::parse_scp(user_host, location)
+                }
+                _ => Err("expected an `https://` forge URL, an scp-style \
+                          `git@<host>:<repository>` remote or a local path"),
+            };
+        };
+        let location = url
+            .split_once(['?', '#'])
+            .map_or(url, |(location, _)| location);
+        let (raw_host, path) = location.split_once('/').unwrap_or((location, ""));
+        let host = canonical_host(raw_host);
+        if host.is_empty() {
+            return Err("URL has no host");
+        }
+        let segments = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+
+        let (forge, repository, kind, rest) = segments
+            .iter()
+            .position(|segment| *segment == "-")
+            .map_or_else(
+                || {
+                    let forge = forge_of_host(&host);
+                    match segments.get(2) {
+                        // groups nest on GitLab so a path without `-` is all repository unless
+                        // it is laid out like GitHub
+                        Some(&kind)
+                            if forge == Forge::GitLab
+                                && !matches!(kind, "tree" | "blob" | "commit") =>
+                        {
+                            (forge, segments.as_slice(), None, None)
+                        }
+                        kind => (
+                            forge,
+                            segments.get(..2).unwrap_or(&[]),
+                            kind,
+                            segments.get(3..),
+                        ),
+                    }
+                },
+                |marker| {
+                    (
+                        Forge::GitLab,
+                        &segments[..marker],
+                        segments.get(marker + 1),
+                        segments.get(marker + 2..),
+                    )
+                },
+            );
+        if repository.len() < 2 {
+            return Err("URL must name an owner and a repository");
+        }
+        let joined_repository = repository.join("/");
+        let repository_path = joined_repository
+            .strip_suffix(".git")
+            .unwrap_or(&joined_repository)
+            .to_owned();
+        let is_commit_kind = match (forge, kind.copied()) {
+            (_, None) => {
+                return Self::checked(forge, Some(host), repository_path, None, "");
+            }
+            (Forge::GitHub | Forge::GitLab, Some("tree" | "blob"))
+            | (Forge::Bitbucket, Some("src")) => false,
+            (Forge::GitHub | Forge::GitLab, Some("commit"))
+            | (Forge::Bitbucket, Some("commits")) => true,
+            _ => {
+                return Err(
+                    "URL must point at a repository, commit, tag or branch e.g. `…/tree/<ref>`",
+                )
+            }
+        };
+        let (git_ref, subpath_segments) = rest
+            .and_then(<[&str]>::split_first)
+            .ok_or("URL must name a commit, tag or branch after the repository")?;
+        if is_commit_kind && !subpath_segments.is_empty() {
+            return Err("commit URLs can't point into the repository");
+        }
+
+        Self::checked(
+            forge,
+            Some(host),
+            repository_path,
+            Some(git_ref),
+            &subpath_segments.join("/"),
+        )
+    }
+
+    /// `[<user>@]<host>:<repository>` with an optional `@<ref>` and `#<subpath>`, which is the
+    /// same repository as the forge's `https://` URL.
+    fn parse_scp(user_host: &str, location: &str) -> result::Result<Self, &'static str> {
+        let host = canonical_host(
+            user_host
+                .rsplit_once('@')
+                .map_or(user_host, |(_, host)| host),
+        );
+        if host.is_empty() {
+            return Err("remote has no host");
+        }
+        let (path_ref, subpath) = location.split_once('#').unwrap_or((location, ""));
+        let (path, git_ref) = path_ref
+            .rsplit_once('@')
+            .map_or((path_ref, None), |(path, git_ref)| (path, Some(git_ref)));
+        if git_ref.is_none() && !subpath.is_empty() {
+            return Err("remotes can only point into the repository at a ref `@<ref>#<subpath>`");
+        }
+        let repository = path.trim_matches('/');
+        if repository
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .count()
+            < 2
+        {
+            return Err("remote must name an owner and a repository");
+        }
+        Self::checked(
+            forge_of_host(&host),
+            Some(host),
+            repository
+                .strip_suffix(".git")
+                .unwrap_or(repository)
+                .to_owned(),
+            git_ref,
+            subpath,
+        )
+    }
+
+    /// `<absolute path>` with an optional `@<ref>` and `#<subpath>`.
+    fn parse_local(location: &str) -> result::Result<Self, &'static str> {
+        let (path_ref, subpath) = location.split_once('#').unwrap_or((location, ""));
+        let (path, git_ref) = path_ref
+            .rsplit_once('@')
+            .map_or((path_ref, None), |(path, git_ref)| (path, Some(git_ref)));
+        if !path.starts_with('/') {
+            return Err("local repository path must be absolute");
+        }
+        let repository = path.trim_end_matches('/');
+        Self::checked(
+            Forge::Local,
+            None,
+            if repository.is_empty() {
+                "/"
+            } else {
+                repository
+            }
+            .to_owned(),
+            git_ref,
+            subpath,
+        )
+    }
+
+    /// Check `git_ref` and `subpath`, dropping empty segments from the latter.
+    fn checked(
+        forge: Forge,
+        host: Option<String>,
+        repository: String,
+        git_ref: Option<&str>,
+        subpath: &str,
+    ) -> result::Result<Self, &'static str> {
+        if git_ref.is_some_and(|named_ref| !is_git_ref(named_ref)) {
+            return Err("ref must be a commit SHA, tag or branch name");
+        }
+        let subpath_segments = subpath
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        if subpath_segments.contains(&"..") {
+            return Err("subpath must stay within the repository");
+        }
+        Ok(Self {
+            forge,
+            host,
+            repository,
+            git_ref: git_ref.map(|named_ref| {
+                if is_commit_sha(named_ref) {
+                    named_ref.to_lowercase()
+                } else {
+                    named_ref.to_owned()
+                }
+            }),
+            subpath: (!subpath_segments.is_empty()).then(|| subpath_segments.join("/")),
+        })
+    }
+}
+
+impl FromStr for SourceReference {
+    type Err = OrcaError;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::try_parse(source).map_err(|reason| {
+            OrcaError::from(Kind::InvalidSourceReference(source.into(), reason.into()))
+        })
+    }
+}
+
+impl Display for SourceReference {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let host = self.host.as_deref().unwrap_or_default();
+        let repository = &self.repository;
+        if self.forge == Forge::Local {
+            write!(f, "file://{repository}")?;
+            if let Some(git_ref) = &self.git_ref {
+                write!(f, "@{git_ref}")?;
+            }
+            if let Some(subpath) = &self.subpath {
+                write!(f, "#{subpath}")?;
+            }
+            return Ok(());
+        }
+
+        write!(f, "https://{host}/{repository}")?;
+        if let Some(git_ref) = &self.git_ref {
+            let layout = match self.forge {
+                Forge::GitLab => "-/tree",
+                Forge::Bitbucket => "src",
+                Forge::GitHub | Forge::Local => "tree",
+            };
+            write!(f, "/{layout}/{git_ref}")?;
+            if let Some(subpath) = &self.subpath {
+                write!(f, "/{subpath}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lowercase `host` without a leading `www.`.
+fn canonical_host(host: &str) -> String {
+    let lowercase_host = host.to_lowercase();
+    lowercase_host
+        .strip_prefix("www.")
+        .unwrap_or(&lowercase_host)
+        .to_owned()
+}
+
+/// Forge whose URL layout `host` uses, GitHub's for hosts that aren't known.
+fn forge_of_host(host: &str) -> Forge {
+    match host {
+        "bitbucket.org" => Forge::Bitbucket,
+        "gitlab.com" => Forge::GitLab,
+        _ => Forge::GitHub,
+    }
+}
+
+/// Full SHA-1 (40) or SHA-256 (64) hex digits in either case.
+fn is_commit_sha(git_ref: &str) -> bool {
+    matches!(git_ref.len(), 40 | 64)
+        && git_ref
+            .chars()
+            .all(|character| character.is_ascii_hexdigit())
+}
+
+/// Loosely `git check-ref-format`: no whitespace, control or special characters and no `..`.
+fn is_git_ref(git_ref: &str) -> bool {
+    !git_ref.is_empty()
+        && !git_ref.contains("..")
+        && !git_ref.starts_with(['-', '.'])
+        && !git_ref.ends_with(['.', '/'])
+        && git_ref.chars().all(|character| {
+            character.is_ascii_graphic()
+                && !matches!(character, '~' | '^' | ':' | '?' | '*' | '[' | '\\' | '@')
+        })
+}
@@ -1,59 +1,230 @@
 use crate::{
     error::{Kind, OrcaError, Result},
-    model::{from_yaml, to_yaml, Annotation, Pod},
-    util::get_type_name,
+    migration::{schema_version, upgrade_spec, SCHEMA_VERSION},
+    model::{
+        from_spec, from_spec_with_secrets, spec_to_canonical, to_canonical, to_yaml, Annotation,
+        HashAlgorithm, Model, SpecFormat, Version,
+    },
 };
 use colored::Colorize;
 use regex::Regex;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
 use std::{
     collections::BTreeMap,
-    fs,
+    fs, io,
     path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
-use super::{ModelID, ModelInfo, Store};
-
-const SPEC_FILE_NAME: &str = "spec.yaml";
-
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
-struct NameVerTreeKey {
-    name: String,
-    version: String,
+use super::{ModelID, ModelInfo, PinPolicy, Store};
+
+const SPEC_FILE_STEM: &str = "spec";
+/// Where a spec rewritten by a migration is kept as it was hashed, relative to the model's
+/// directory.
+const ORIGINAL_SPEC_FILE_STEM: &str = "original/spec";
+const INDEX_DIRECTORY: &str = "index";
+/// Longest file name most file systems accept, in bytes.
+const MAX_FILE_NAME_LENGTH: usize = 255;
+
+/// Annotated models of one type by name and then version, stored as one file per annotation at
+/// `<directory>/index/<class>/<name>/<version>.yaml` with the name and version encoded like in
+/// annotation file names.
+type NameVerIndex = BTreeMap<String, BTreeMap<Version, IndexedModel>>;
+
+/// Tells apart the temporary files of index writes running at the same time in this process.
+static TEMPORARY_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// What the name/version index records about an annotation.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct IndexedModel {
+    /// Directory the model is stored under, a bare SHA-256 digest in older stores.
+    hash: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, String>,
 }
 
 /// Local storage system for orca items implmenting store
+///
+/// Models are stored in directories named by their prefixed hash e.g. `pod/sha256:13d6...`.
+/// Windows file systems don't allow `:` in file names, so the store has to be on a file system
+/// that does e.g. any Linux or macOS one.
 #[derive(Debug)]
 pub struct LocalFileStore {
     directory: PathBuf,
+    hash_algorithm: HashAlgorithm,
+    rewrite_migrated: bool,
+    pin_policy: PinPolicy,
+    verify_hashes: bool,
 }
 
 impl Store for LocalFileStore {
-    fn save_pod(&self, pod: &Pod) -> Result<()> {
-        self.save_model(pod, &pod.hash, pod.annotation.as_ref())
+    fn save<T: Model>(&self, model: &T) -> Result<()> {
+        self.pin_policy.enforce(model)?;
+        if let Some(annotation) = model.annotation() {
+            check_annotation_name(&annotation.name, &annotation.version)?;
+        }
+        // Secret references aren't hashed so they are kept with the annotation instead of the spec
+        let secret_env_yaml = secret_env_yaml(model)?;
+        if secret_env_yaml.is_some() && model.annotation().is_none() {
+            return Err(OrcaError::from(
+                Kind::UnannotatedSecretRefs(T::class_name()),
+            ));
+        }
+        // References to other models are made with the algorithm those are hashed with, so only
+        // models of the store's algorithm can be resolved later
+        if HashAlgorithm::split(model.hash())?.0 != self.hash_algorithm {
+            return Err(OrcaError::from(Kind::HashAlgorithmMismatch(
+                T::class_name(),
+                model.hash().to_owned(),
+                self.hash_algorithm.to_string(),
+            )));
+        }
+        let hash = self.hash_algorithm.digest(&to_canonical(model)?);
+        if let Some(value) = model.annotation() {
+            self.check_annotation_free::<T>(&value.name, &value.version, &hash)?;
+        }
+
+        // Save the model first, a spec already stored in any format counts as saved as long as it
+        // still hashes the same
+        let (spec_path, format) = self.find_spec::<T>(&hash, SPEC_FILE_STEM);
+        if self.verify_hashes && spec_path.exists() {
+            let spec = fs::read_to_string(&spec_path)?;
+            let stored = from_spec::<T>(&spec, format, &hash, None)?;
+            self.verify_spec(&hash, &spec_path, &spec, format, &stored)?;
+        }
+        Self::save_file(spec_path, &to_yaml::<T>(model)?, false)?;
+
+        // Save the annotation file and throw and error if exist
+        if let Some(value) = model.annotation() {
+            // Annotation exist, thus save it
+            let annotation_path =
+                self.make_annotation_path::<T>(&hash, &value.name, &value.version);
+            Self::save_file(&annotation_path, &serde_yaml::to_string(value)?, true)?;
+            let secrets_path = self.make_secrets_path::<T>(&hash, &value.name, &value.version);
+            if let Some(yaml) = &secret_env_yaml {
+                Self::save_file(&secrets_path, yaml, true)?;
+            }
+            if let Err(error) = self.write_index_entry::<T>(
+                &value.name,
+                &value.version,
+                &IndexedModel {
+                    hash,
+                    labels: value.labels.clone(),
+                },
+            ) {
+                // e.g. another store saved a different model under the annotation in the meantime
+                fs::remove_file(annotation_path)?;
+                if secrets_path.exists() {
+                    fs::remove_file(secrets_path)?;
+                }
+                return Err(error);
+            }
+        }
+
+        Ok(())
     }
 
-    fn load_pod(&self, model_id: &ModelID) -> Result<Pod> {
-        self.load_model::<Pod>(model_id)
+    fn load<T: Model>(&self, model_id: &ModelID) -> Result<T> {
+        match model_id {
+            ModelID::NameVer(name, _) | ModelID::Latest(name) | ModelID::VersionReq(name, _) => {
+                // Search the name-ver index
+                let (version, hash) = self.get_hash_from_name_ver_tree::<T>(name, model_id)?;
+
+                // Get the spec along with its annotation and secret references yaml
+                let annotation_yaml =
+                    fs::read_to_string(self.make_annotation_path::<T>(&hash, name, &version))?;
+                let secrets_path = self.make_secrets_path::<T>(&hash, name, &version);
+                let secret_env_yaml = secrets_path
+                    .exists()
+                    .then(|| fs::read_to_string(secrets_path))
+                    .transpose()?;
+
+                self.read_model::<T>(&hash, Some(&annotation_yaml), secret_env_yaml.as_deref())
+            }
+            ModelID::Hash(hash_prefix) => {
+                let hash = self.get_hash_from_prefix::<T>(hash_prefix)?;
+
+                // Secret references are only stored per annotation and a model loaded without them
+                // would run without its secrets
+                if fs::read_dir(self.make_path::<T>(&hash, "secrets"))
+                    .is_ok_and(|mut entries| entries.next().is_some())
+                {
+                    return Err(OrcaError::from(Kind::SecretRefsNeedAnnotation(
+                        T::class_name(),
+                        hash,
+                    )));
+                }
+
+                // Get the spec
+                self.read_model::<T>(&hash, None, None)
+            }
+        }
     }
 
-    /// Return Btree where key is column name and value is a vec of values
-    /// Are we okay with returning a bunch of strings? For now it works but later on like adding version
-    /// this will break...
-    fn list_pod(&self) -> Result<Vec<ModelInfo>> {
-        self.list_model::<Pod>()
+    fn list<T: Model>(&self) -> Result<Vec<ModelInfo>> {
+        Ok(self
+            .load_index::<T>()?
+            .iter()
+            .flat_map(|(name, versions)| {
+                versions.iter().map(|(version, indexed)| ModelInfo {
+                    name: name.clone(),
+                    version: version.clone(),
+                    hash: qualify_hash(&indexed.hash),
+                    labels: indexed.labels.clone(),
+                })
+            })
+            .collect())
     }
 
-    fn delete_pod(&self, model_id: &ModelID) -> Result<()> {
-        self.delete_model::<Pod>(model_id)
+    fn delete<T: Model>(&self, model_id: &ModelID) -> Result<()> {
+        let hash = match model_id {
+            ModelID::NameVer(name, _) | ModelID::Latest(name) | ModelID::VersionReq(name, _) => {
+                // Search the name-ver index
+                self.get_hash_from_name_ver_tree::<T>(name, model_id)?.1
+            }
+            ModelID::Hash(hash_prefix) => self.get_hash_from_prefix::<T>(hash_prefix)?,
+        };
+
+        // Index entries are found through the annotations of the model
+        self.ensure_index::<T>()?;
+        let annotations_directory = self.make_path::<T>(&hash, "annotations");
+        let mut annotations = Vec::new();
+        if annotations_directory.is_dir() {
+            for entry in fs::read_dir(annotations_directory)? {
+                let path = entry?.path();
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                if let Some((name, version)) =
+                    decode_annotation_file_stem(&stem).and_then(|(name, raw_version)| {
+                        Some((name, Version::parse(&raw_version).ok()?))
+                    })
+                {
+                    annotations.push((name, version));
+                }
+            }
+        }
+
+        fs::remove_dir_all(self.make_dir_path::<T>(&hash))?;
+        for (name, version) in annotations {
+            self.remove_index_entry::<T>(&name, &version, &hash)?;
+        }
+        Ok(())
     }
 
-    fn delete_annotation<T>(&self, name: &str, version: &str) -> Result<()> {
+    fn delete_annotation<T: Model>(&self, name: &str, version: &Version) -> Result<()> {
         // Search the name ver index for the hash
-        let hash = self.get_hash_from_name_ver_tree::<T>(name, version)?;
+        let (_, hash) = self.get_hash_from_name_ver_tree::<T>(
+            name,
+            &ModelID::NameVer(name.into(), version.clone()),
+        )?;
 
         fs::remove_file(self.make_annotation_path::<T>(&hash, name, version))?;
-        Ok(())
+        let secrets_path = self.make_secrets_path::<T>(&hash, name, version);
+        if secrets_path.exists() {
+            fs::remove_file(secrets_path)?;
+        }
+        self.remove_index_entry::<T>(name, version, &hash)
     }
 }
 
@@ -62,165 +233,485 @@ impl LocalFileStore {
     pub fn new(directory: impl AsRef<Path>) -> Self {
         Self {
             directory: directory.as_ref().into(),
+            hash_algorithm: HashAlgorithm::default(),
+            rewrite_migrated: false,
+            pin_policy: PinPolicy::default(),
+            verify_hashes: true,
         }
     }
 
+    /// Key saved models by `hash_algorithm` instead of the default. Only models hashed with the
+    /// same algorithm can be saved e.g. pods built with `PodBuilder::hash_algorithm`.
+    #[must_use]
+    pub const fn with_hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+
+    /// Write specs from an older schema version back to disk once they are upgraded on load. The
+    /// models keep the hash they were stored under.
+    #[must_use]
+    pub const fn with_migration_rewrite(mut self, rewrite_migrated: bool) -> Self {
+        self.rewrite_migrated = rewrite_migrated;
+        self
+    }
+
+    /// Decide what happens when a pod whose image or source isn't pinned is saved. Warns by
+    /// default.
+    #[must_use]
+    pub const fn with_pin_policy(mut self, pin_policy: PinPolicy) -> Self {
+        self.pin_policy = pin_policy;
+        self
+    }
+
+    /// Check that stored specs still hash to the hash they are stored under when they are loaded
+    /// or saved again. Specs of an older schema version are checked as they were written. On by
+    /// default.
+    #[must_use]
+    pub const fn with_hash_verification(mut self, verify_hashes: bool) -> Self {
+        self.verify_hashes = verify_hashes;
+        self
+    }
+
     /// Getter function for directory
     pub fn get_directory(&self) -> &Path {
         &self.directory
     }
 
-    fn make_dir_path<T>(&self, hash: &str) -> PathBuf {
+    fn make_dir_path<T: Model>(&self, hash: &str) -> PathBuf {
         PathBuf::from(format!(
             "{}/{}/{}",
             self.directory.to_string_lossy(),
-            get_type_name::<T>(),
+            T::class_name(),
             hash,
         ))
     }
 
     /// Helper function for making path to a given item type T
-    pub fn make_path<T>(&self, hash: &str, file_name: &str) -> PathBuf {
+    pub fn make_path<T: Model>(&self, hash: &str, file_name: &str) -> PathBuf {
         self.make_dir_path::<T>(hash).join(file_name)
     }
 
-    /// Helper function to create the path to the annotations files
-    pub fn make_annotation_path<T>(&self, hash: &str, name: &str, version: &str) -> PathBuf {
+    /// Helper function to create the path to the annotations files. Names are written as is if
+    /// they only contain ASCII letters, digits, `-`, `_` and spaces, anything else is
+    /// percent-encoded e.g. `vision/style.transfer` as `vision%2Fstyle%2Etransfer`.
+    pub fn make_annotation_path<T: Model>(
+        &self,
+        hash: &str,
+        name: &str,
+        version: &Version,
+    ) -> PathBuf {
         self.make_dir_path::<T>(hash)
             .join("annotations")
-            .join(format!("{name}-{version}.yaml"))
+            .join(format!(
+                "{}.yaml",
+                encode_annotation_file_stem(name, version)
+            ))
     }
 
-    // Generic function for save load list delete
-    /// Generic func to save all sorts of item
-    ///
-    /// Example usage inside `LocalFileStore`
-    /// ``` markdown
-    /// let pod = Pod::new(); // For example doesn't actually work
-    /// self.save_item(pod, &pod.annotation, &pod.hash).unwrap()
-    /// ```
-    fn save_model<T: Serialize>(
+    /// Helper function to create the path to the secret references stored along with an
+    /// annotation, named like the annotation file.
+    pub fn make_secrets_path<T: Model>(
         &self,
-        item: &T,
         hash: &str,
-        annotation: Option<&Annotation>,
-    ) -> Result<()> {
-        // Save the item first
-        Self::save_file(
-            self.make_path::<T>(hash, SPEC_FILE_NAME),
-            &to_yaml::<T>(item)?,
-            false,
+        name: &str,
+        version: &Version,
+    ) -> PathBuf {
+        self.make_dir_path::<T>(hash).join("secrets").join(format!(
+            "{}.yaml",
+            encode_annotation_file_stem(name, version)
+        ))
+    }
+
+    /// Locate the spec stored under `hash` as e.g. `spec.yaml`, `spec.json` or `spec.toml` in
+    /// that order of preference. Falls back to where a YAML spec would be if there is none.
+    fn find_spec<T: Model>(&self, hash: &str, file_stem: &str) -> (PathBuf, SpecFormat) {
+        SpecFormat::ALL
+            .into_iter()
+            .map(|format| {
+                (
+                    self.make_path::<T>(hash, &format!("{file_stem}.{}", format.extension())),
+                    format,
+                )
+            })
+            .find(|(path, _)| path.exists())
+            .unwrap_or_else(|| {
+                (
+                    self.make_path::<T>(hash, &format!("{file_stem}.yaml")),
+                    SpecFormat::Yaml,
+                )
+            })
+    }
+
+    /// Read the model stored under `hash` after checking that its spec still hashes to `hash`.
+    /// The spec is rewritten in place if it was upgraded to the current schema version and
+    /// `rewrite_migrated` is set, keeping the original for verification.
+    fn read_model<T: Model>(
+        &self,
+        hash: &str,
+        annotation_yaml: Option<&str>,
+        secret_env_yaml: Option<&str>,
+    ) -> Result<T> {
+        let (path, format) = self.find_spec::<T>(hash, SPEC_FILE_STEM);
+        let spec = fs::read_to_string(&path)?;
+        let model = from_spec_with_secrets::<T>(
+            &spec,
+            format,
+            &qualify_hash(hash),
+            annotation_yaml,
+            secret_env_yaml,
         )?;
+        if self.verify_hashes {
+            self.verify_spec(hash, &path, &spec, format, &model)?;
+        }
+        if self.rewrite_migrated {
+            if let Some(upgraded_spec) = upgrade_spec::<T>(&spec, format)? {
+                Self::save_file(
+                    self.make_path::<T>(
+                        hash,
+                        &format!("{ORIGINAL_SPEC_FILE_STEM}.{}", format.extension()),
+                    ),
+                    &spec,
+                    true,
+                )?;
+                fs::write(&path, upgraded_spec)?;
+            }
+        }
+        Ok(model)
+    }
 
-        // Save the annotation file and throw and error if exist
-        if let Some(value) = annotation {
-            // Annotation exist, thus save it
-            Self::save_file(
-                self.make_annotation_path::<T>(hash, &value.name, &value.version),
-                &serde_yaml::to_string(value)?,
-                true,
-            )?;
+    /// Check that `spec` read from `path` into `model` hashes to `hash`. A spec rewritten by a
+    /// migration is checked through the original it was upgraded from.
+    fn verify_spec<T: Model>(
+        &self,
+        hash: &str,
+        path: &Path,
+        spec: &str,
+        format: SpecFormat,
+        model: &T,
+    ) -> Result<()> {
+        let expected = qualify_hash(hash);
+        let (algorithm, _) = HashAlgorithm::split(&expected)?;
+        if spec_hashes_to(hash, spec, format, model)? {
+            return Ok(());
         }
 
-        Ok(())
+        let (original_path, original_format) = self.find_spec::<T>(hash, ORIGINAL_SPEC_FILE_STEM);
+        if original_path.exists() {
+            let original_spec = fs::read_to_string(&original_path)?;
+            let original = from_spec::<T>(&original_spec, original_format, &expected, None)?;
+            if spec_hashes_to(hash, &original_spec, original_format, &original)?
+                && to_canonical(&original)? == to_canonical(model)?
+            {
+                return Ok(());
+            }
+        }
+        Err(OrcaError::from(Kind::HashMismatch(
+            path.to_path_buf(),
+            expected,
+            algorithm.digest(&to_canonical(model)?),
+        )))
     }
 
-    fn list_model<T>(&self) -> Result<Vec<ModelInfo>> {
-        let name_ver_tree = self.build_name_ver_tree::<T>()?;
-        let mut models = Vec::with_capacity(name_ver_tree.len());
-        for (key, hash) in name_ver_tree {
-            models.push(ModelInfo {
-                name: key.name,
-                version: key.version,
-                hash,
-            });
+    /// Rebuild the name/version index of models of type `T` from the annotation files in the
+    /// store. Only needed for annotation files added or removed by hand since the index is kept
+    /// up to date on save and delete and built on first use in stores that don't have one.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an annotation file is misnamed, names a malformed version or can't be
+    /// read, or if the index can't be written.
+    pub fn rebuild_index<T: Model>(&self) -> Result<()> {
+        let staging_path = self.write_staged_index::<T>(&self.scan_annotations::<T>()?)?;
+        let index_path = self.index_path::<T>();
+        let stale_path = staging_path.with_extension("stale");
+        if index_path.exists() {
+            fs::rename(&index_path, &stale_path)?;
         }
+        fs::rename(&staging_path, &index_path)?;
+        if stale_path.exists() {
+            fs::remove_dir_all(stale_path)?;
+        }
+        Ok(())
+    }
 
-        Ok(models)
+    /// Directory of the name/version index of models of type `T`.
+    fn index_path<T: Model>(&self) -> PathBuf {
+        self.directory.join(INDEX_DIRECTORY).join(T::class_name())
     }
 
-    /// Generic function for loading spec.yaml into memory
-    fn load_model<T: DeserializeOwned>(&self, model_id: &ModelID) -> Result<T> {
-        match model_id {
-            ModelID::NameVer(name, version) => {
-                // Search the name-ver index
-                let hash = self.get_hash_from_name_ver_tree::<T>(name, version)?;
+    /// Directory of the index entries of every version of the annotations called `name`.
+    fn index_name_path<T: Model>(&self, name: &str) -> PathBuf {
+        self.index_path::<T>().join(encode_annotation_name(name))
+    }
 
-                // Get the spec and annotation yaml
-                let spec_yaml = fs::read_to_string(self.make_path::<T>(&hash, SPEC_FILE_NAME))?;
+    fn index_entry_path<T: Model>(&self, name: &str, version: &Version) -> PathBuf {
+        self.index_name_path::<T>(name)
+            .join(format!("{}.yaml", encode_version(&version.to_string())))
+    }
 
-                let annotation_yaml =
-                    fs::read_to_string(self.make_annotation_path::<T>(&hash, name, version))?;
+    /// Build the name/version index of models of type `T` from the annotation files if the store
+    /// has none yet e.g. because an older release wrote it.
+    fn ensure_index<T: Model>(&self) -> Result<()> {
+        let index_path = self.index_path::<T>();
+        if index_path.is_dir() {
+            return Ok(());
+        }
+        let staging_path = self.write_staged_index::<T>(&self.scan_annotations::<T>()?)?;
+        match fs::rename(&staging_path, &index_path) {
+            Ok(()) => Ok(()),
+            // another store built it in the meantime
+            Err(_) if index_path.is_dir() => Ok(fs::remove_dir_all(staging_path)?),
+            Err(error) => Err(error.into()),
+        }
+    }
 
-                from_yaml::<T>(&spec_yaml, &hash, Some(&annotation_yaml))
+    /// Write `index` into a new directory next to the index directory of models of type `T` so
+    /// that it can be moved into place at once.
+    fn write_staged_index<T: Model>(&self, index: &NameVerIndex) -> Result<PathBuf> {
+        let staging_path = self.directory.join(INDEX_DIRECTORY).join(format!(
+            ".{}-{}",
+            T::class_name(),
+            unique_suffix()
+        ));
+        fs::create_dir_all(&staging_path)?;
+        for (name, versions) in index {
+            let name_path = staging_path.join(encode_annotation_name(name));
+            fs::create_dir_all(&name_path)?;
+            for (version, indexed) in versions {
+                fs::write(
+                    name_path.join(format!("{}.yaml", encode_version(&version.to_string()))),
+                    serde_yaml::to_string(indexed)?,
+                )?;
             }
-            ModelID::Hash(hash) => {
-                // Get the spec and annotation yaml
-                let spec_yaml = fs::read_to_string(self.make_path::<T>(hash, SPEC_FILE_NAME))?;
-                from_yaml::<T>(&spec_yaml, hash, None)
+        }
+        Ok(staging_path)
+    }
+
+    /// Every annotated model of type `T`, reading one index entry per annotation.
+    fn load_index<T: Model>(&self) -> Result<NameVerIndex> {
+        self.ensure_index::<T>()?;
+        let mut index = NameVerIndex::new();
+        for entry in fs::read_dir(self.index_path::<T>())? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .and_then(|file_name| percent_decode(file_name.to_str()?))
+                .filter(|name| path.ends_with(encode_annotation_name(name)))
+                .ok_or_else(|| OrcaError::from(Kind::InvalidAnnotationFile(path.clone())))?;
+            let versions = self.load_index_versions::<T>(&name)?;
+            if !versions.is_empty() {
+                index.insert(name, versions);
             }
         }
+        Ok(index)
     }
 
-    fn delete_model<T>(&self, model_id: &ModelID) -> Result<()> {
-        let hash = match model_id {
-            ModelID::NameVer(name, version) => {
-                // Search the name-ver index
-                self.get_hash_from_name_ver_tree::<T>(name, version)?
+    /// Index entries of every version of the annotations called `name`, without reading those of
+    /// other names.
+    fn load_index_versions<T: Model>(&self, name: &str) -> Result<BTreeMap<Version, IndexedModel>> {
+        self.ensure_index::<T>()?;
+        let mut versions = BTreeMap::new();
+        let entries = match fs::read_dir(self.index_name_path::<T>(name)) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(versions),
+            Err(error) => return Err(error.into()),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            // skip temporary files of writes in progress
+            let Some(encoded_version) = path
+                .file_name()
+                .and_then(|file_name| file_name.to_str()?.strip_suffix(".yaml"))
+            else {
+                continue;
+            };
+            let Some(raw_version) = percent_decode(encoded_version)
+                .filter(|raw_version| encode_version(raw_version) == encoded_version)
+            else {
+                return Err(OrcaError::from(Kind::InvalidAnnotationFile(path)));
+            };
+            let version = Version::parse(&raw_version)
+                .map_err(|error| OrcaError::from(Kind::InvalidVersion(raw_version, error)))?;
+            // deleted since the directory was read
+            if let Some(indexed) = read_index_entry(&path)? {
+                versions.insert(version, indexed);
             }
-            ModelID::Hash(hash) => hash.to_owned(),
+        }
+        Ok(versions)
+    }
+
+    /// Fail if the annotation `name` and `version` is indexed under another directory than `hash`,
+    /// which saving would leave with two annotation files. This includes the bare hex directory
+    /// of the same hash from older stores.
+    fn check_annotation_free<T: Model>(
+        &self,
+        name: &str,
+        version: &Version,
+        hash: &str,
+    ) -> Result<()> {
+        self.ensure_index::<T>()?;
+        match read_index_entry(&self.index_entry_path::<T>(name, version))? {
+            Some(indexed) if indexed.hash != hash => Err(OrcaError::from(Kind::AnnotationTaken(
+                T::class_name(),
+                format!("{name}:{version}"),
+                qualify_hash(&indexed.hash),
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record an annotation in the index, going through a temporary file so that readers never
+    /// see a partial entry. Entries are separate files so saves running at the same time don't
+    /// overwrite each other's, and an entry is only replaced by one for the same hash.
+    fn write_index_entry<T: Model>(
+        &self,
+        name: &str,
+        version: &Version,
+        indexed: &IndexedModel,
+    ) -> Result<()> {
+        let path = self.index_entry_path::<T>(name, version);
+        fs::create_dir_all(self.index_name_path::<T>(name))?;
+        let temporary_path = path.with_extension(format!("{}.tmp", unique_suffix()));
+        fs::write(&temporary_path, serde_yaml::to_string(indexed)?)?;
+        // Linking, unlike renaming, fails instead of replacing an entry saved in the meantime
+        let written = match fs::hard_link(&temporary_path, &path) {
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => self
+                .check_annotation_free::<T>(name, version, &indexed.hash)
+                .and_then(|()| Ok(fs::rename(&temporary_path, &path)?)),
+            linked => linked.map_err(OrcaError::from),
         };
+        if temporary_path.exists() {
+            fs::remove_file(temporary_path)?;
+        }
+        written
+    }
 
-        fs::remove_dir_all(self.make_dir_path::<T>(&hash))?;
+    /// Remove an annotation from the index unless it was since saved for another hash.
+    fn remove_index_entry<T: Model>(
+        &self,
+        name: &str,
+        version: &Version,
+        hash: &str,
+    ) -> Result<()> {
+        let path = self.index_entry_path::<T>(name, version);
+        if read_index_entry(&path)?.is_some_and(|indexed| indexed.hash == hash) {
+            fs::remove_file(path)?;
+        }
         Ok(())
     }
 
-    fn build_name_ver_tree<T>(&self) -> Result<BTreeMap<NameVerTreeKey, String>> {
-        // Construct the cache with glob and regex
-        let type_name = get_type_name::<T>();
+    /// Build the name/version index of models of type `T` by walking their annotation files.
+    fn scan_annotations<T: Model>(&self) -> Result<NameVerIndex> {
+        // Match annotation paths with glob and regex, the name and version are decoded from the
+        // file name separately so that a malformed version is reported instead of skipped
+        let type_name = T::class_name();
         let re = Regex::new(&format!(
-            r"^.*\/{type_name}\/(?<hash>[a-z0-9]+)\/annotations\/(?<name>[A-z0-9\- ]+)-(?<ver>[0-9]+.[0-9]+.[0-9]+).yaml$"
+            r"^.*\/{type_name}\/(?<hash>[a-z0-9:]+)\/annotations\/(?<stem>[^\/]+)\.yaml$"
         ))?;
 
-        // Create tree where name_ver is key and value is hash
-        let mut name_ver_tree = BTreeMap::new();
+        let mut index = NameVerIndex::new();
 
         let search_pattern = self.make_dir_path::<T>("*").join("annotations/*");
 
-        for path in glob::glob(&search_pattern.to_string_lossy())? {
-            let path_str: String = path?.to_string_lossy().to_string();
+        for entry in glob::glob(&search_pattern.to_string_lossy())? {
+            let path = entry?;
+            let path_str: String = path.to_string_lossy().to_string();
 
-            let Some(cap) = re.captures(&path_str) else {
-                continue; // Add the no regex nomatch back
+            let Some((cap, (name, raw_version))) = re.captures(&path_str).and_then(|cap| {
+                let name_version = decode_annotation_file_stem(cap.name("stem")?.as_str())?;
+                Some((cap, name_version))
+            }) else {
+                return Err(OrcaError::from(Kind::InvalidAnnotationFile(path)));
             };
-
-            name_ver_tree.insert(
-                NameVerTreeKey {
-                    name: cap["name"].to_string(),
-                    version: cap["ver"].to_string(),
+            let version = Version::parse(&raw_version)
+                .map_err(|error| OrcaError::from(Kind::InvalidVersion(raw_version, error)))?;
+            let annotation: Annotation = serde_yaml::from_str(&fs::read_to_string(&path)?)?;
+
+            index.entry(name).or_default().insert(
+                version,
+                IndexedModel {
+                    hash: cap["hash"].into(),
+                    labels: annotation.labels,
                 },
-                cap["hash"].into(),
             );
         }
 
-        Ok(name_ver_tree)
+        Ok(index)
     }
 
-    fn get_hash_from_name_ver_tree<T>(&self, name: &str, version: &str) -> Result<String> {
-        Ok(self
-            .build_name_ver_tree::<T>()?
-            .get(&NameVerTreeKey {
-                name: name.to_owned(),
-                version: version.to_owned(),
-            })
-            .ok_or_else(|| {
-                OrcaError::from(Kind::NoAnnotationFound(
-                    get_type_name::<T>(),
-                    name.into(),
-                    version.into(),
-                ))
-            })?
-            .to_owned())
+    /// Resolve a name-based `model_id` to the (version, hash) of the annotation it refers to.
+    fn get_hash_from_name_ver_tree<T: Model>(
+        &self,
+        name: &str,
+        model_id: &ModelID,
+    ) -> Result<(Version, String)> {
+        let found = if let ModelID::NameVer(_, version) = model_id {
+            // Only the one entry has to be read
+            self.ensure_index::<T>()?;
+            read_index_entry(&self.index_entry_path::<T>(name, version))?
+                .map(|indexed| (version.clone(), indexed.hash))
+        } else {
+            let versions = self.load_index_versions::<T>(name)?;
+            model_id
+                .select_version(versions.keys())
+                .map(|version| (version.clone(), versions[version].hash.clone()))
+        };
+        found.ok_or_else(|| {
+            OrcaError::from(Kind::NoAnnotationFound(
+                T::class_name(),
+                model_id.to_string(),
+            ))
+        })
+    }
+
+    /// Expand a full hash or a unique prefix of one (like git does) to the directory it is
+    /// stored under. A prefix with an algorithm e.g. `sha256:13d6` only matches hashes of that
+    /// algorithm while a bare one e.g. `13d6` matches the digest of any algorithm.
+    fn get_hash_from_prefix<T: Model>(&self, hash_prefix: &str) -> Result<String> {
+        // Anything else could name a directory outside of the model's e.g. `..`
+        if !is_hash_prefix(hash_prefix) {
+            return Err(OrcaError::from(Kind::InvalidHashPrefix(
+                hash_prefix.to_owned(),
+            )));
+        }
+        if self.make_dir_path::<T>(hash_prefix).is_dir() {
+            return Ok(hash_prefix.to_owned());
+        }
+
+        let type_directory = self.directory.join(T::class_name());
+        let mut candidates = Vec::new();
+        if type_directory.is_dir() {
+            for entry in fs::read_dir(type_directory)? {
+                let hash = entry?.file_name().to_string_lossy().to_string();
+                let is_match = if hash_prefix.contains(':') {
+                    qualify_hash(&hash).starts_with(hash_prefix)
+                } else {
+                    hash.split_once(':')
+                        .map_or(hash.as_str(), |(_, digest)| digest)
+                        .starts_with(hash_prefix)
+                };
+                if is_match {
+                    candidates.push(hash);
+                }
+            }
+        }
+
+        match candidates.len() {
+            0 => Err(OrcaError::from(Kind::NoHashMatch(
+                T::class_name(),
+                hash_prefix.to_owned(),
+            ))),
+            1 => Ok(candidates.remove(0)),
+            _ => {
+                candidates.sort();
+                Err(OrcaError::from(Kind::AmbiguousHashPrefix(
+                    T::class_name(),
+                    hash_prefix.to_owned(),
+                    candidates,
+                )))
+            }
+        }
     }
 
     // Help save file function
@@ -252,3 +743,175 @@ impl LocalFileStore {
         Ok(())
     }
 }
+
+/// Reject annotation names that can't be told apart once stored or don't fit in a file name.
+fn check_annotation_name(name: &str, version: &Version) -> Result<()> {
+    let reason = if name.is_empty() {
+        "it is empty"
+    } else if name.trim() != name {
+        "it starts or ends with whitespace"
+    } else if name.chars().any(char::is_control) {
+        "it contains control characters"
+    } else if encode_annotation_file_stem(name, version).len() + ".yaml".len()
+        > MAX_FILE_NAME_LENGTH
+    {
+        "it is too long to be stored as a file name"
+    } else {
+        return Ok(());
+    };
+    Err(OrcaError::from(Kind::InvalidAnnotationName(
+        name.to_owned(),
+        reason.to_owned(),
+    )))
+}
+
+/// `<name>-<version>` percent-encoded by `encode_annotation_name` and `encode_version`. Since `.`
+/// is always encoded in the name the first one in the stem is in the version, which makes the `-`
+/// before the version the last one ahead of it.
+fn encode_annotation_file_stem(name: &str, version: &Version) -> String {
+    format!(
+        "{}-{}",
+        encode_annotation_name(name),
+        encode_version(&version.to_string())
+    )
+}
+
+/// Percent-encode every character of `name` except for lowercase ASCII letters, digits, `-`, `_`
+/// and spaces. Upper case letters are encoded so that names differing only in case are different
+/// files on case-insensitive file systems such as the macOS default.
+fn encode_annotation_name(name: &str) -> String {
+    percent_encode(name, |character| {
+        character.is_ascii_lowercase()
+            || character.is_ascii_digit()
+            || matches!(character, '-' | '_' | ' ')
+    })
+}
+
+/// Percent-encode the upper case letters a version's pre-release or build metadata may have, for
+/// the same reason as in `encode_annotation_name`.
+fn encode_version(raw_version: &str) -> String {
+    percent_encode(raw_version, |character| !character.is_ascii_uppercase())
+}
+
+/// Replace every character of `text` that `keep` rejects with its UTF-8 bytes as `%XX`.
+fn percent_encode(text: &str, keep: impl Fn(char) -> bool) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for character in text.chars() {
+        if keep(character) {
+            encoded.push(character);
+        } else {
+            for byte in character.encode_utf8(&mut [0; 4]).bytes() {
+                encoded.push('%');
+                encoded.extend(
+                    [byte >> 4, byte & 0xF]
+                        .into_iter()
+                        .filter_map(|nibble| char::from_digit(u32::from(nibble), 16))
+                        .map(|digit| digit.to_ascii_uppercase()),
+                );
+            }
+        }
+    }
+    encoded
+}
+
+/// Undo `percent_encode`, `None` if an escape is malformed or the bytes aren't UTF-8.
+fn percent_decode(encoded: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded;
+    while let Some((plain, escaped)) = rest.split_once('%') {
+        bytes.extend(plain.bytes());
+        bytes.push(u8::from_str_radix(escaped.get(..2)?, 16).ok()?);
+        rest = escaped.get(2..)?;
+    }
+    bytes.extend(rest.bytes());
+    String::from_utf8(bytes).ok()
+}
+
+/// Split an annotation file stem into its decoded name and raw version, `None` if it isn't in the
+/// form written by `encode_annotation_file_stem`.
+fn decode_annotation_file_stem(stem: &str) -> Option<(String, String)> {
+    let (before_minor, _) = stem.split_once('.')?;
+    let (encoded_name, _) = before_minor.rsplit_once('-')?;
+    let encoded_version = stem.strip_prefix(encoded_name)?.strip_prefix('-')?;
+    let name = percent_decode(encoded_name)?;
+    let raw_version = percent_decode(encoded_version)?;
+
+    // Only accept the one spelling the name is written with e.g. not `%61` for `a`
+    (encode_annotation_name(&name) == encoded_name
+        && encode_version(&raw_version) == encoded_version)
+        .then_some((name, raw_version))
+}
+
+/// Whether `spec` read into `model` hashes to `hash` the way it was hashed when it was written:
+///
+/// - as the canonical encoding of `model` for specs of the current schema version,
+/// - as the canonical encoding of the spec itself for specs of an older schema version or
+/// - as the spec file itself in stores from before the canonical encoding, which name
+///   directories by the bare SHA-256 digest.
+fn spec_hashes_to<T: Model>(hash: &str, spec: &str, format: SpecFormat, model: &T) -> Result<bool> {
+    let expected = qualify_hash(hash);
+    let (algorithm, _) = HashAlgorithm::split(&expected)?;
+    if algorithm.digest(&to_canonical(model)?) == expected {
+        return Ok(true);
+    }
+
+    let spec_mapping = format.parse(spec)?;
+    if schema_version::<T>(&spec_mapping)? < SCHEMA_VERSION
+        && algorithm.digest(&spec_to_canonical(&spec_mapping, model)?) == expected
+    {
+        return Ok(true);
+    }
+    Ok(!hash.contains(':') && algorithm.digest(spec) == expected)
+}
+
+/// Index entry stored at `path`, `None` if there is none e.g. because it was just removed.
+fn read_index_entry(path: &Path) -> Result<Option<IndexedModel>> {
+    match fs::read_to_string(path) {
+        Ok(yaml) => Ok(Some(serde_yaml::from_str(&yaml)?)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Suffix for temporary paths that no other store writing at the same time uses.
+fn unique_suffix() -> String {
+    format!(
+        "{}-{}",
+        process::id(),
+        TEMPORARY_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Secret references of `model` as YAML, `None` if it has none.
+fn secret_env_yaml<T: Serialize>(model: &T) -> Result<Option<String>> {
+    match serde_yaml::to_value(model)?.get("secret_env") {
+        Some(Value::Mapping(secret_env)) if !secret_env.is_empty() => {
+            Ok(Some(serde_yaml::to_string(secret_env)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Whether `hash_prefix` is a lowercase alphanumeric digest (prefix) with an optional
+/// `<algorithm>:` in front, the only names a model's directory can have.
+fn is_hash_prefix(hash_prefix: &str) -> bool {
+    let is_token = |token: &str| {
+        !token.is_empty()
+            && token
+                .chars()
+                .all(|character| character.is_ascii_lowercase() || character.is_ascii_digit())
+    };
+    hash_prefix.split_once(':').map_or_else(
+        || is_token(hash_prefix),
+        |(algorithm, digest)| is_token(algorithm) && is_token(digest),
+    )
+}
+
+/// Stores from before hashes were prefixed name directories by the bare SHA-256 digest.
+fn qualify_hash(hash: &str) -> String {
+    if hash.contains(':') {
+        hash.to_owned()
+    } else {
+        format!("{}:{hash}", HashAlgorithm::Sha256)
+    }
+}
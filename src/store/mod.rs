@@ -1,57 +1,302 @@
-use crate::{error::Result, model::Pod};
+use crate::{
+    compare::{self, ModelDiff},
+    error::{Kind, OrcaError, Result},
+    model::{Model, Pod, Version, VersionReq},
+};
+use colored::Colorize;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
 
 /// Enum for identification to
 pub enum ModelID {
     /// Identification via name and version
-    NameVer(String, String),
-    /// Identification by hash
+    NameVer(String, Version),
+    /// Identification via name, resolving to the highest release stored. Pre-releases e.g.
+    /// `1.3.0-rc.1` are skipped unless the name has nothing but pre-releases, like `VersionReq`
+    /// only matches them when asked for explicitly.
+    Latest(String),
+    /// Identification via name, resolving to the highest stored version that satisfies the
+    /// requirement e.g. `^1.2` or `>=0.67, <1.0`
+    VersionReq(String, VersionReq),
+    /// Identification by hash or a unique prefix of it
     Hash(String),
 }
 
+impl ModelID {
+    /// Pick the version this id refers to out of the stored `versions` of its name. Stores
+    /// resolve name-based ids through this so they all agree on what e.g. "latest" means.
+    /// Always `None` for `Hash`.
+    pub fn select_version<'a>(
+        &self,
+        versions: impl IntoIterator<Item = &'a Version>,
+    ) -> Option<&'a Version> {
+        let mut candidates = versions.into_iter();
+        match self {
+            Self::NameVer(_, version) => candidates.find(|candidate| *candidate == version),
+            Self::Latest(_) => {
+                let (pre_releases, releases): (Vec<_>, Vec<_>) =
+                    candidates.partition(|candidate| !candidate.pre.is_empty());
+                releases
+                    .into_iter()
+                    .max()
+                    .or_else(|| pre_releases.into_iter().max())
+            }
+            Self::VersionReq(_, version_req) => candidates
+                .filter(|candidate| version_req.matches(candidate))
+                .max(),
+            Self::Hash(_) => None,
+        }
+    }
+}
+
+impl Display for ModelID {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::NameVer(name, version) => write!(f, "{name}:{version}"),
+            Self::Latest(name) => write!(f, "{name}:latest"),
+            Self::VersionReq(name, version_req) => write!(f, "{name}:{version_req}"),
+            Self::Hash(hash) => write!(f, "{hash}"),
+        }
+    }
+}
+
 /// Struct for list functios
+#[derive(Debug)]
 pub struct ModelInfo {
     /// Name from annotation of the model struct
     pub name: String,
     /// Version from annotation from model struct
-    pub version: String,
+    pub version: Version,
     /// Hash of the model struct
     pub hash: String,
+    /// Labels from annotation of the model struct
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Requirements on annotation labels e.g. `team=vision,stage!=dev,gpu`.
+///
+/// Written like Kubernetes equality-based selectors where a bare key only requires the label to be
+/// present. A model matches if it meets every requirement.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LabelSelector {
+    requirements: Vec<LabelRequirement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LabelRequirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    Exists(String),
+}
+
+impl LabelSelector {
+    /// Whether `labels` meet every requirement of the selector.
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        self.requirements
+            .iter()
+            .all(|requirement| match requirement {
+                LabelRequirement::Equals(key, value) => labels.get(key) == Some(value),
+                LabelRequirement::NotEquals(key, value) => labels.get(key) != Some(value),
+                LabelRequirement::Exists(key) => labels.contains_key(key),
+            })
+    }
 }
 
-/// Standard behavior of any store backend supported.
+impl FromStr for LabelSelector {
+    type Err = OrcaError;
+
+    fn from_str(selector: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            OrcaError::from(Kind::InvalidLabelSelector(selector.into(), reason.into()))
+        };
+        let mut requirements = Vec::new();
+        for term in selector.split(',').map(str::trim) {
+            let requirement = match term.split_once('=') {
+                Some((key, value)) if value.contains('=') => {
+                    return Err(invalid(&format!(
+                        "`{term}` compares `{key}` more than once"
+                    )))
+                }
+                Some((key, value)) => key.strip_suffix('!').map_or_else(
+                    || LabelRequirement::Equals(key.trim().into(), value.trim().into()),
+                    |negated_key| {
+                        LabelRequirement::NotEquals(negated_key.trim().into(), value.trim().into())
+                    },
+                ),
+                None => LabelRequirement::Exists(term.into()),
+            };
+            let (LabelRequirement::Equals(key, _)
+            | LabelRequirement::NotEquals(key, _)
+            | LabelRequirement::Exists(key)) = &requirement;
+            if key.is_empty() {
+                return Err(invalid("every term needs a label key"));
+            }
+            requirements.push(requirement);
+        }
+        Ok(Self { requirements })
+    }
+}
+
+/// What a store does when asked to save a model such as a pod whose image isn't pinned to a digest
+/// or whose source isn't pinned to a commit SHA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PinPolicy {
+    /// Save it without comment.
+    Allow,
+    /// Save it but print a warning since a tag or branch may later point at different content.
+    #[default]
+    Warn,
+    /// Refuse to save it.
+    Require,
+}
+
+impl PinPolicy {
+    /// Apply the policy to the image and source references of `model`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` unless the policy is `Allow` if the references of `model` can't be
+    /// parsed, or if the policy is `Require` and any isn't pinned (all problems are reported
+    /// together).
+    pub fn enforce(self, model: &impl Model) -> Result<()> {
+        if self == Self::Allow {
+            return Ok(());
+        }
+        let mut unpinned = model.unpinned_references()?;
+
+        match self {
+            Self::Allow => Ok(()),
+            Self::Warn => {
+                for error in unpinned {
+                    println!("{} {error}", "Warning:".bright_yellow());
+                }
+                Ok(())
+            }
+            Self::Require => match unpinned.len() {
+                0 => Ok(()),
+                1 => Err(unpinned.remove(0)),
+                _ => Err(OrcaError::from(Kind::ValidationErrors(unpinned))),
+            },
+        }
+    }
+}
+
+/// Standard behavior of any store backend supported. Name-based `ModelID`s are resolved against
+/// the annotations held by the store using `ModelID::select_version`.
+///
+/// Backends only implement the generic methods e.g. `save::<PodJob>`. The ones named after pods
+/// are shorthands kept for existing callers.
 pub trait Store {
+    /// How a model is stored.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if there is an issue storing `model` or its references break the
+    /// store's pin policy.
+    fn save<T: Model>(&self, model: &T) -> Result<()>;
+    /// How to load a stored model into a model instance.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if there is an issue loading a model from the store using `model_id`.
+    fn load<T: Model>(&self, model_id: &ModelID) -> Result<T>;
+    /// How to query stored models of a type, sorted by name and then semantic version.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if there is an issue querying metadata from existing models in the
+    /// store.
+    fn list<T: Model>(&self) -> Result<Vec<ModelInfo>>;
+    /// Stored models of a type whose annotation labels match `selector`, sorted like `list`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if there is an issue querying metadata from existing models in the
+    /// store.
+    fn list_by_labels<T: Model>(&self, selector: &LabelSelector) -> Result<Vec<ModelInfo>> {
+        Ok(self
+            .list::<T>()?
+            .into_iter()
+            .filter(|model_info| selector.matches(&model_info.labels))
+            .collect())
+    }
+    /// How to delete a stored model (does not propagate).
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if there is an issue deleting a model from the store using `model_id`.
+    fn delete<T: Model>(&self, model_id: &ModelID) -> Result<()>;
+    /// Compare two stored models field by field. Ids that resolve by name include the annotation
+    /// in the comparison while hash ids load without one.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if there is an issue loading either of `old` and `new`.
+    fn diff<T: Model>(&self, old: &ModelID, new: &ModelID) -> Result<ModelDiff> {
+        compare::diff(&self.load::<T>(old)?, &self.load::<T>(new)?)
+    }
+    /// How to delete only annotation, which will leave the item untouched
+    ///
+    /// # Errors
+    /// Will return `Err` if there is an issue of finding the annotation and deleting it
+    fn delete_annotation<T: Model>(&self, name: &str, version: &Version) -> Result<()>;
+
     /// How a pod is stored.
     ///
     /// # Errors
     ///
-    /// Will return `Err` if there is an issue storing `pod`.
-    fn save_pod(&self, pod: &Pod) -> Result<()>;
+    /// Will return `Err` if there is an issue storing `pod` or its image breaks the store's pin
+    /// policy.
+    fn save_pod(&self, pod: &Pod) -> Result<()> {
+        self.save(pod)
+    }
     /// How to load a stored pod into a model instance.
     ///
     /// # Errors
     ///
     /// Will return `Err` if there is an issue loading a pod from the store using `name` and
     /// `version`.
-    fn load_pod(&self, model_id: &ModelID) -> Result<Pod>;
-    /// How to query stored pods.
+    fn load_pod(&self, model_id: &ModelID) -> Result<Pod> {
+        self.load(model_id)
+    }
+    /// How to query stored pods, sorted by name and then semantic version.
     ///
     /// # Errors
     ///
     /// Will return `Err` if there is an issue querying metadata from existing pods in the store.
-    fn list_pod(&self) -> Result<Vec<ModelInfo>>;
+    fn list_pod(&self) -> Result<Vec<ModelInfo>> {
+        self.list::<Pod>()
+    }
+    /// Stored pods whose annotation labels match `selector`, sorted like `list_pod`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if there is an issue querying metadata from existing pods in the store.
+    fn list_pod_by_labels(&self, selector: &LabelSelector) -> Result<Vec<ModelInfo>> {
+        self.list_by_labels::<Pod>(selector)
+    }
     /// How to delete a stored pod (does not propagate).
     ///
     /// # Errors
     ///
     /// Will return `Err` if there is an issue deleting a pod from the store using `name` and
     /// `version`.
-    fn delete_pod(&self, model_id: &ModelID) -> Result<()>;
-
-    /// How to delete only annotation, which will leave the item untouched
+    fn delete_pod(&self, model_id: &ModelID) -> Result<()> {
+        self.delete::<Pod>(model_id)
+    }
+    /// Compare two stored pods field by field. Ids that resolve by name include the annotation
+    /// in the comparison while hash ids load without one.
     ///
     /// # Errors
-    /// Will return `Err` if there is an issue of finding the annotation and deleting it
-    fn delete_annotation<T>(&self, name: &str, version: &str) -> Result<()>;
+    ///
+    /// Will return `Err` if there is an issue loading either of `old` and `new`.
+    fn diff_pod(&self, old: &ModelID, new: &ModelID) -> Result<ModelDiff> {
+        self.diff::<Pod>(old, new)
+    }
 }
+
 /// Store implementation on a local filesystem.
 pub mod filestore;
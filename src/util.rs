@@ -1,5 +1,7 @@
-use sha2::{Digest, Sha256};
-use std::any::type_name;
+use std::{
+    any::type_name,
+    path::{Component, Path, PathBuf},
+};
 
 #[expect(
     clippy::unwrap_used,
@@ -14,6 +16,21 @@ pub fn get_type_name<T>() -> String {
         .to_lowercase()
 }
 
-pub fn hash(buffer: &str) -> String {
-    format!("{:x}", Sha256::digest(buffer))
+/// Lexically resolve `.` and `..` without touching the file system.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            Component::Prefix(_) | Component::RootDir | Component::Normal(_) => {
+                normalized.push(component);
+            }
+        }
+    }
+    normalized
 }
@@ -1,110 +1,189 @@
+use chrono::DateTime;
 use orcapod::error::Result;
-use orcapod::store::ModelInfo;
 use orcapod::{
-    model::{to_yaml, Annotation, Pod, StreamInfo},
-    store::{filestore::LocalFileStore, ModelID, Store},
+    model::{
+        Annotation, Blob, BlobKind, Edge, Input, Model, Pipeline, Pod, PodBuilder, PodJob,
+        PodResult, Status, StreamInfo, StreamRef, Version,
+    },
+    store::filestore::LocalFileStore,
 };
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fs,
     ops::{Deref, DerefMut},
     path::PathBuf,
 };
 use tempfile::tempdir;
 
-#[derive(PartialEq, Clone)]
-pub enum Model {
-    Pod(Pod),
+/// `item` with its annotation renamed to `name`.
+pub fn renamed<T: Model>(item: &T, name: &str) -> Result<T> {
+    let mut value = serde_yaml::to_value(item)?;
+    value["annotation"]["name"] = name.into();
+    Ok(serde_yaml::from_value(value)?)
 }
 
-pub enum ModelType {
-    Pod,
+pub fn get_test_pod() -> Result<Pod> {
+    get_test_pod_builder()?.build()
 }
 
-impl Model {
-    #[expect(clippy::unwrap_used, reason = "test")]
-    pub fn get_name(&self) -> &str {
-        match self {
-            Self::Pod(pod) => &pod.annotation.as_ref().unwrap().name,
-        }
-    }
-
-    pub fn get_hash(&self) -> &str {
-        match self {
-            Self::Pod(pod) => &pod.hash,
-        }
-    }
-
-    #[expect(clippy::unwrap_used, reason = "test")]
-    pub fn get_version(&self) -> &str {
-        match self {
-            Self::Pod(pod) => &pod.annotation.as_ref().unwrap().version,
-        }
-    }
-
-    pub const fn is_annotation_none(&self) -> bool {
-        match self {
-            Self::Pod(pod) => pod.annotation.is_none(),
-        }
-    }
-
-    pub fn to_yaml(&self) -> Result<String> {
-        match self {
-            Self::Pod(pod) => to_yaml(pod),
-        }
-    }
-
-    #[expect(clippy::unwrap_used, reason = "test")]
-    pub fn set_name(&mut self, name: &str) {
-        match self {
-            Self::Pod(pod) => name.clone_into(&mut pod.annotation.as_mut().unwrap().name),
-        }
-    }
+pub fn get_test_pod_builder() -> Result<PodBuilder> {
+    Ok(Pod::builder()
+        .annotation(Annotation {
+            name: "style-transfer".to_owned(),
+            description: "This is an example pod.".to_owned(),
+            version: Version::new(0, 67, 0),
+            labels: BTreeMap::from([
+                ("team".to_owned(), "vision".to_owned()),
+                ("stage".to_owned(), "prod".to_owned()),
+            ]),
+            authors: vec!["Jane Doe <jane@example.com>".to_owned()],
+            license: Some("MIT".to_owned()),
+            created_at: DateTime::from_timestamp(1_727_740_800, 0), // 2024-10-01T00:00:00Z
+        })
+        .source_commit_url("https://github.com/zenml-io/zenml/tree/0.67.0")
+        .image("zenmldocker/zenml-server:0.67.0")
+        .command("tail -f /dev/null")
+        .input_stream(
+            "painting",
+            StreamInfo {
+                path: PathBuf::from("/input/painting.png"),
+                match_pattern: "/input/painting.png".to_owned(),
+                ..StreamInfo::default()
+            },
+        )
+        .input_stream(
+            "image",
+            StreamInfo {
+                path: PathBuf::from("/input/image.png"),
+                match_pattern: "/input/image.png".to_owned(),
+                ..StreamInfo::default()
+            },
+        )
+        .output_dir("/output")
+        .output_stream(
+            "styled",
+            StreamInfo {
+                path: PathBuf::from("./styled.png"),
+                match_pattern: "./styled.png".to_owned(),
+                ..StreamInfo::default()
+            },
+        )
+        .recommended_cpus("250m".parse()?)
+        .recommended_memory("2Gi".parse()?))
 }
 
-pub fn get_test_item(item_type: &ModelType) -> Result<Model> {
-    match item_type {
-        ModelType::Pod => Ok(Model::Pod(get_test_pod()?)),
-    }
+pub fn get_test_pod_job() -> Result<PodJob> {
+    get_test_pod_job_of(&get_test_pod()?)
 }
 
-pub fn get_test_pod() -> Result<Pod> {
-    Pod::new(
+pub fn get_test_pod_job_of(pod: &Pod) -> Result<PodJob> {
+    PodJob::new(
         Some(Annotation {
-            name: "style-transfer".to_owned(),
-            description: "This is an example pod.".to_owned(),
-            version: "0.67.0".to_owned(),
+            name: "style-transfer-job".to_owned(),
+            description: "This is an example pod job.".to_owned(),
+            version: Version::new(0, 1, 0),
+            labels: BTreeMap::new(),
+            authors: vec![],
+            license: None,
+            created_at: None,
         }),
-        "https://github.com/zenml-io/zenml/tree/0.67.0".to_owned(),
-        "zenmldocker/zenml-server:0.67.0".to_owned(),
-        "tail -f /dev/null".to_owned(),
+        pod,
         BTreeMap::from([
             (
                 "painting".to_owned(),
-                StreamInfo {
-                    path: PathBuf::from("/input/painting.png"),
-                    match_pattern: "/input/painting.png".to_owned(),
-                },
+                Input::Blob(Blob {
+                    kind: BlobKind::File,
+                    location: PathBuf::from("/data/paintings/starry-night.png"),
+                    checksum: "8a6e5f1c0c2b3d4e".to_owned(),
+                }),
             ),
             (
                 "image".to_owned(),
-                StreamInfo {
-                    path: PathBuf::from("/input/image.png"),
-                    match_pattern: "/input/image.png".to_owned(),
-                },
+                Input::Blob(Blob {
+                    kind: BlobKind::File,
+                    location: PathBuf::from("/data/images/cat.png"),
+                    checksum: "4f2d8e9a1b7c6d5e".to_owned(),
+                }),
             ),
         ]),
-        PathBuf::from("/output"),
+        "500m".parse()?,
+        "1Gi".parse()?,
+    )
+}
+
+pub fn get_test_pod_result() -> Result<PodResult> {
+    get_test_pod_result_of(&get_test_pod()?, &get_test_pod_job()?)
+}
+
+pub fn get_test_pod_result_of(pod: &Pod, pod_job: &PodJob) -> Result<PodResult> {
+    PodResult::new(
+        Some(Annotation {
+            name: "style-transfer-result".to_owned(),
+            description: "This is an example pod result.".to_owned(),
+            version: Version::new(0, 1, 0),
+            labels: BTreeMap::new(),
+            authors: vec![],
+            license: None,
+            created_at: None,
+        }),
+        pod,
+        pod_job,
+        Status::Completed,
+        Some(0),
+        1_727_740_800, // 2024-10-01T00:00:00Z
+        1_727_740_862,
         BTreeMap::from([(
             "styled".to_owned(),
-            StreamInfo {
-                path: PathBuf::from("./styled.png"),
-                match_pattern: "./styled.png".to_owned(),
-            },
+            vec![Blob {
+                kind: BlobKind::File,
+                location: PathBuf::from("/data/results/styled.png"),
+                checksum: "c3b2a1f0e9d8c7b6".to_owned(),
+            }],
         )]),
-        0.25,                // 250 millicores as frac cores
-        (2_u64) * (1 << 30), // 2GiB in bytes
-        None,
+    )
+}
+
+pub fn stream_ref(node: &str, stream: &str) -> StreamRef {
+    StreamRef {
+        node: node.to_owned(),
+        stream: stream.to_owned(),
+    }
+}
+
+/// Two style transfers in sequence where the second restyles the output of the first.
+pub fn get_test_pipeline(pod: &Pod) -> Result<Pipeline> {
+    Pipeline::new(
+        Some(Annotation {
+            name: "double-style-transfer".to_owned(),
+            description: "This is an example pipeline.".to_owned(),
+            version: Version::new(0, 1, 0),
+            labels: BTreeMap::new(),
+            authors: vec![],
+            license: None,
+            created_at: None,
+        }),
+        &BTreeMap::from([
+            ("first".to_owned(), pod.clone()),
+            ("second".to_owned(), pod.clone()),
+        ]),
+        vec![Edge {
+            source: stream_ref("first", "styled"),
+            target: stream_ref("second", "image"),
+        }],
+        BTreeMap::from([
+            (
+                "image".to_owned(),
+                BTreeSet::from([stream_ref("first", "image")]),
+            ),
+            (
+                "painting".to_owned(),
+                BTreeSet::from([
+                    stream_ref("first", "painting"),
+                    stream_ref("second", "painting"),
+                ]),
+            ),
+        ]),
+        BTreeMap::from([("styled".to_owned(), stream_ref("second", "styled"))]),
     )
 }
 
@@ -129,7 +208,9 @@ impl DerefMut for TestLocalStore {
 #[expect(clippy::expect_used, reason = "test")]
 impl Drop for TestLocalStore {
     fn drop(&mut self) {
-        fs::remove_dir_all(self.store.get_directory()).expect("Failed to teardown store.");
+        if self.store.get_directory().exists() {
+            fs::remove_dir_all(self.store.get_directory()).expect("Failed to teardown store.");
+        }
     }
 }
 
@@ -137,56 +218,19 @@ impl Drop for TestLocalStore {
 impl TestLocalStore {
     /// # Panics
     /// Will panic if fail to fetch stuff related to annotation
-    pub fn make_annotation_path(&self, item: &Model) -> PathBuf {
-        match item {
-            Model::Pod(pod) => self.store.make_annotation_path::<Pod>(
-                &pod.hash,
-                &pod.annotation.as_ref().unwrap().name,
-                &pod.annotation.as_ref().unwrap().version,
-            ),
-        }
-    }
-
-    pub fn make_path(&self, model_type: &ModelType, hash: &str, file_name: &str) -> PathBuf {
-        match model_type {
-            ModelType::Pod => self.store.make_path::<Pod>(hash, file_name),
-        }
-    }
-
-    pub fn save_model(&self, model: &Model) -> Result<()> {
-        match model {
-            Model::Pod(pod) => self.store.save_pod(pod),
-        }
-    }
-
-    pub fn load_model(&self, model_type: &ModelType, item_key: &ModelID) -> Result<Model> {
-        match model_type {
-            ModelType::Pod => Ok(Model::Pod(self.store.load_pod(item_key)?)),
-        }
-    }
-
-    pub fn list_model(&self, model_type: &ModelType) -> Result<Vec<ModelInfo>> {
-        match model_type {
-            ModelType::Pod => self.store.list_pod(),
-        }
-    }
-
-    pub fn delete_item(&self, model_type: &ModelType, item_key: &ModelID) -> Result<()> {
-        match model_type {
-            ModelType::Pod => self.store.delete_pod(item_key),
-        }
+    pub fn make_annotation_path<T: Model>(&self, item: &T) -> PathBuf {
+        let annotation = item.annotation().unwrap();
+        self.store
+            .make_annotation_path::<T>(item.hash(), &annotation.name, &annotation.version)
     }
+}
 
-    pub fn delete_item_annotation(
-        &mut self,
-        item_type: &ModelType,
-        name: &str,
-        version: &str,
-    ) -> Result<()> {
-        match item_type {
-            ModelType::Pod => Ok(self.store.delete_annotation::<Pod>(name, version)?),
-        }
-    }
+pub fn store_test_with(
+    configure: impl FnOnce(LocalFileStore) -> LocalFileStore,
+) -> Result<TestLocalStore> {
+    Ok(TestLocalStore {
+        store: configure(LocalFileStore::new(tempdir()?.path())),
+    })
 }
 
 pub fn store_test(store_directory: Option<&str>) -> Result<TestLocalStore> {
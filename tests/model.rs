@@ -1,26 +1,858 @@
 #![expect(clippy::panic_in_result_fn, reason = "Panics OK in tests.")]
 
 pub mod fixture;
-use fixture::get_test_pod;
+use fixture::{
+    get_test_pipeline, get_test_pod, get_test_pod_builder, get_test_pod_job, get_test_pod_result,
+    stream_ref,
+};
 use indoc::indoc;
-use orcapod::error::Result;
-use orcapod::model::{to_yaml, Pod};
+use orcapod::compare::diff;
+use orcapod::error::{OrcaError, Result};
+use orcapod::gpu::{ComputeCapability, GPUDevice, GPUMismatch, GPURequirement, GPUVendor};
+use orcapod::images::ImageReference;
+use orcapod::migration::{migrate, Migration};
+use orcapod::model::{
+    from_spec, from_yaml, to_canonical, to_json, to_spec, to_toml, to_yaml, Annotation, Blob,
+    BlobKind, Cardinality, Edge, Input, Pipeline, Pod, PodJob, PodResult, SpecFormat, Status,
+    StreamInfo, StreamRef, Version,
+};
+use orcapod::quantity::{Cpu, Memory};
+use orcapod::secrets::{EnvSecretProvider, FileSecretProvider, SecretRef};
+use orcapod::sources::{Forge, SourceReference};
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::PathBuf,
+};
 
 #[test]
 fn verify_hash() -> Result<()> {
     assert_eq!(
         get_test_pod()?.hash,
-        "13d69656d396c272588dd875b2802faee1a56bd985e3c43c7db276a373bc9ddb"
+        "sha256:6f9a810dd556dea713a8649096ddb58c416830bc57ddd42387c0d2d8268d0d3f"
     );
     Ok(())
 }
 
+#[test]
+fn verify_pod_to_canonical() -> Result<()> {
+    assert_eq!(
+        to_canonical::<Pod>(&get_test_pod()?)?,
+        concat!(
+            r#"{"class":"pod","command":"tail -f /dev/null","image":"zenmldocker/zenml-server:0.67.0","#,
+            r#""input_stream_map":{"image":{"match_pattern":"/input/image.png","path":"/input/image.png"},"#,
+            r#""painting":{"match_pattern":"/input/painting.png","path":"/input/painting.png"}},"#,
+            r#""output_dir":"/output","output_stream_map":{"styled":{"match_pattern":"./styled.png","#,
+            r#""path":"./styled.png"}},"recommended_cpus":"250m","recommended_memory":"2Gi","#,
+            r#""required_gpu":null,"source_commit_url":"https://github.com/zenml-io/zenml/tree/0.67.0"}"#
+        )
+    );
+    Ok(())
+}
+
+#[derive(Serialize)]
+enum Shape {
+    Unit,
+    Newtype(u8),
+    Tuple(u8, u8),
+    Struct { value: u8 },
+}
+
+#[derive(Serialize)]
+struct Sample {
+    annotation: Option<String>,
+    hash: String,
+    text: String,
+    ratio: f32,
+    whole: f64,
+    big: u64,
+    negative: i8,
+    missing: Option<u8>,
+    shapes: Vec<Shape>,
+    nested: BTreeMap<String, bool>,
+}
+
+#[test]
+fn verify_canonical_encoding_rules() -> Result<()> {
+    let sample = Sample {
+        annotation: Some("ignored".to_owned()),
+        hash: "ignored".to_owned(),
+        text: "quote \" backslash \\ tab \t ünï".to_owned(),
+        ratio: 0.1,
+        whole: 2.0,
+        big: u64::MAX,
+        negative: -7,
+        missing: None,
+        shapes: vec![
+            Shape::Unit,
+            Shape::Newtype(1),
+            Shape::Tuple(1, 2),
+            Shape::Struct { value: 3 },
+        ],
+        nested: BTreeMap::from([("b".to_owned(), false), ("a".to_owned(), true)]),
+    };
+    assert_eq!(
+        to_canonical(&sample)?,
+        concat!(
+            r#"{"big":18446744073709551615,"class":"sample","missing":null,"negative":-7,"#,
+            r#""nested":{"a":true,"b":false},"ratio":0.1,"#,
+            r#""shapes":["Unit",{"Newtype":1},{"Tuple":[1,2]},{"Struct":{"value":3}}],"#,
+            r#""text":"quote \" backslash \\ tab \u0009 ünï","whole":2}"#
+        )
+    );
+
+    let error = to_canonical(&Sample {
+        ratio: f32::NAN,
+        ..sample
+    })
+    .expect_err("NaN has no canonical encoding");
+    assert!(
+        error.to_string().contains("Non-finite float"),
+        "Unexpected error message: {error}"
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_quantities_parse_and_write_canonically() -> Result<()> {
+    for (quantity, millicores, canonical) in [
+        ("250m", 250, "250m"),
+        ("0.25", 250, "250m"),
+        ("2", 2000, "2"),
+        ("1500m", 1500, "1500m"),
+        ("1.5", 1500, "1500m"),
+    ] {
+        let cpu = quantity.parse::<Cpu>()?;
+        assert_eq!(
+            cpu.as_millicores(),
+            millicores,
+            "Unexpected CPU for `{quantity}`."
+        );
+        assert_eq!(cpu.to_string(), canonical);
+    }
+    for (quantity, bytes, canonical) in [
+        ("512Mi", 512 << 20, "512Mi"),
+        ("2Gi", 2 << 30, "2Gi"),
+        ("0.5Gi", 512 << 20, "512Mi"),
+        ("1G", 1_000_000_000, "1G"),
+        ("1024", 1024, "1Ki"),
+        ("1500", 1500, "1500"),
+    ] {
+        let memory = quantity.parse::<Memory>()?;
+        assert_eq!(
+            memory.as_bytes(),
+            bytes,
+            "Unexpected memory for `{quantity}`."
+        );
+        assert_eq!(memory.to_string(), canonical);
+    }
+    for quantity in ["", "-1", "0.0001", "1.m", "2 Gi", "1e3"] {
+        assert!(
+            quantity.parse::<Cpu>().is_err() && quantity.parse::<Memory>().is_err(),
+            "`{quantity}` should not parse."
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn verify_quantities_accept_numbers_and_add_up() -> Result<()> {
+    assert_eq!(
+        serde_yaml::from_str::<Cpu>("0.25")?,
+        Cpu::from_millicores(250)
+    );
+    assert_eq!(serde_yaml::from_str::<Cpu>("2")?, Cpu::from_cores(2));
+    assert_eq!(
+        serde_yaml::from_str::<Memory>("2147483648")?,
+        "2Gi".parse::<Memory>()?
+    );
+    assert_eq!(
+        serde_yaml::to_string(&Memory::from_bytes(2 << 30))?,
+        "2Gi\n"
+    );
+
+    let cpus = ["250m", "750m", "1"]
+        .into_iter()
+        .map(str::parse::<Cpu>)
+        .sum::<Result<Cpu>>()?;
+    assert_eq!(cpus, Cpu::from_cores(2));
+    assert_eq!(cpus - "500m".parse()?, "1500m".parse()?);
+    assert_eq!(Cpu::from_cores(1).checked_sub(cpus), None);
+    assert_eq!("1Gi".parse::<Memory>()? + "1Gi".parse()?, "2Gi".parse()?);
+
+    // Arithmetic saturates instead of overflowing
+    assert_eq!(Cpu::from_cores(1) - cpus, Cpu::ZERO);
+    assert_eq!(Memory::ZERO - "1Gi".parse()?, Memory::ZERO);
+    let most_memory = Memory::from_bytes(u64::MAX);
+    assert_eq!(most_memory + "1Gi".parse()?, most_memory);
+    assert_eq!(
+        Cpu::from_cores(u64::MAX),
+        Cpu::from_millicores(u64::MAX) + Cpu::from_cores(1)
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_gpu_requirement_matches_inventory() -> Result<()> {
+    let inventory = [
+        GPUDevice {
+            vendor: GPUVendor::NVIDIA,
+            model: "NVIDIA A100-SXM4-80GB".to_owned(),
+            memory: "80Gi".parse()?,
+            compute_capability: Some("8.0".parse()?),
+        },
+        GPUDevice {
+            vendor: GPUVendor::NVIDIA,
+            model: "Tesla T4".to_owned(),
+            memory: "16Gi".parse()?,
+            compute_capability: Some("7.5".parse()?),
+        },
+        GPUDevice {
+            vendor: GPUVendor::AMD,
+            model: "Instinct MI250X".to_owned(),
+            memory: "128Gi".parse()?,
+            compute_capability: None,
+        },
+    ];
+    let requirement = GPURequirement {
+        vendor: GPUVendor::Any,
+        model: None,
+        min_memory: Some("40Gi".parse()?),
+        min_compute_capability: Some("8.0".parse()?),
+        count: 1,
+    };
+    let gpu_match = requirement.match_inventory(&inventory);
+    assert!(gpu_match.is_satisfied(), "A100 should satisfy: {gpu_match}");
+    assert_eq!(gpu_match.qualifying(), vec![0]);
+    let explanation = gpu_match.to_string();
+    for expected in [
+        "1 of 3 GPU(s) qualify, 1 required.",
+        "GPU 0 (NVIDIA A100-SXM4-80GB): qualifies",
+        "GPU 1 (Tesla T4): has 16Gi of memory but 40Gi is required, compute capability 7.5 is below 8.0",
+        "GPU 2 (Instinct MI250X): has no compute capability but 8.0 is required",
+    ] {
+        assert!(
+            explanation.contains(expected),
+            "Missing `{expected}` in: {explanation}"
+        );
+    }
+
+    let amd_requirement = GPURequirement {
+        vendor: GPUVendor::AMD,
+        model: Some("a100".to_owned()),
+        min_memory: None,
+        min_compute_capability: None,
+        count: 1,
+    };
+    let amd_match = amd_requirement.match_inventory(&inventory);
+    assert!(!amd_match.is_satisfied(), "No AMD A100: {amd_match}");
+    assert_eq!(
+        amd_match.devices()[0].1,
+        vec![GPUMismatch::Vendor(GPUVendor::AMD, GPUVendor::NVIDIA)],
+        "Model should match case-insensitively."
+    );
+    assert_eq!(
+        amd_match.devices()[2].1,
+        vec![GPUMismatch::Model(
+            "a100".to_owned(),
+            "Instinct MI250X".to_owned()
+        )]
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_compute_capability_parses_and_orders() -> Result<()> {
+    let compute_capability = "8.6".parse::<ComputeCapability>()?;
+    assert_eq!(compute_capability, ComputeCapability { major: 8, minor: 6 });
+    assert_eq!(compute_capability.to_string(), "8.6");
+    assert!(compute_capability > "8.0".parse()? && compute_capability < "9.0".parse()?);
+    assert!(
+        "8".parse::<ComputeCapability>()
+            .expect_err("Minor version is required")
+            .to_string()
+            .contains("is not a valid compute capability"),
+        "Unexpected error message."
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_image_reference_parts() -> Result<()> {
+    let image = get_test_pod()?.image_reference()?;
+    assert_eq!(
+        image,
+        ImageReference {
+            registry: None,
+            repository: "zenmldocker/zenml-server".to_owned(),
+            tag: Some("0.67.0".to_owned()),
+            digest: None,
+        }
+    );
+    assert_eq!(image.registry_or_default(), "docker.io");
+    assert!(!image.is_pinned(), "A tag alone is mutable.");
+
+    let pinned = concat!(
+        "localhost:5000/zenml-io/zenml:0.67.0",
+        "@sha256:7d1e2a9bfb4e5b4c3e1f0a5d2c8b6e4f3a2d1c0b9a8f7e6d5c4b3a2918273645"
+    );
+    let image = pinned.parse::<ImageReference>()?;
+    assert_eq!(image.registry.as_deref(), Some("localhost:5000"));
+    assert_eq!(image.repository, "zenml-io/zenml");
+    assert_eq!(image.tag.as_deref(), Some("0.67.0"));
+    assert!(image.is_pinned(), "Digest should pin the image.");
+    assert_eq!(image.to_string(), pinned);
+
+    for (invalid, reason) in [
+        ("ZenML/server", "repository must be lowercase"),
+        ("zenml/server:", "tag must be"),
+        ("zenml/server@sha256:abc", "digest must be"),
+        ("bad_host.io:x/zenml", "registry must be"),
+    ] {
+        let message = invalid
+            .parse::<ImageReference>()
+            .expect_err("Reference should be rejected")
+            .to_string();
+        assert!(
+            message.contains(reason),
+            "Expected `{reason}` for `{invalid}`: {message}"
+        );
+    }
+
+    let message = get_test_pod_builder()?
+        .image("zenml/server:bad tag")
+        .build()
+        .expect_err("Pod with an invalid image should not be built")
+        .to_string();
+    assert!(
+        message.contains("is not a valid reference"),
+        "Unexpected error message: {message}"
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_source_reference_parts() -> Result<()> {
+    let source = get_test_pod()?.source_reference()?;
+    assert_eq!(
+        source,
+        SourceReference {
+            forge: Forge::GitHub,
+            host: Some("github.com".to_owned()),
+            repository: "zenml-io/zenml".to_owned(),
+            git_ref: Some("0.67.0".to_owned()),
+            subpath: None,
+        }
+    );
+    assert!(!source.is_pinned(), "A tag can be moved.");
+
+    for (spelling, canonical) in [
+        (
+            "https://gitlab.com/group/sub/project/-/blob/main/src/train.py",
+            "https://gitlab.com/group/sub/project/-/tree/main/src/train.py",
+        ),
+        (
+            "https://bitbucket.org/team/repo/commits/8A3A5BA8F4C5D3E2B1A0F9E8D7C6B5A4F3E2D1C0",
+            "https://bitbucket.org/team/repo/src/8a3a5ba8f4c5d3e2b1a0f9e8d7c6b5a4f3e2d1c0",
+        ),
+        (
+            "file:///srv/git/zenml/@v1.0#/src//zenml/",
+            "file:///srv/git/zenml@v1.0#src/zenml",
+        ),
+        ("/srv/git/zenml/", "file:///srv/git/zenml"),
+        (
+            "git@github.com:zenml-io/zenml.git",
+            "https://github.com/zenml-io/zenml",
+        ),
+        (
+            "git@GitLab.com:group/sub/project.git@main#src/train.py",
+            "https://gitlab.com/group/sub/project/-/tree/main/src/train.py",
+        ),
+        (
+            "https://gitlab.com/group/sub/project.git",
+            "https://gitlab.com/group/sub/project",
+        ),
+        (
+            "https://git.example.com/team/repo/tree/main",
+            "https://git.example.com/team/repo/tree/main",
+        ),
+    ] {
+        assert_eq!(
+            spelling.parse::<SourceReference>()?.to_string(),
+            canonical,
+            "Unexpected canonical form of `{spelling}`"
+        );
+    }
+
+    for (invalid, reason) in [
+        (
+            "github.com/zenml-io/zenml",
+            "expected an `https://` forge URL",
+        ),
+        (
+            "https://github.com/zenml-io",
+            "must name an owner and a repository",
+        ),
+        (
+            "https://github.com/zenml-io/zenml/pulls",
+            "must point at a repository, commit, tag or branch",
+        ),
+        (
+            "https://git.example.com/team/repo/src/main",
+            "must point at a repository, commit, tag or branch",
+        ),
+        ("file://srv/git/zenml@main", "must be absolute"),
+        (
+            "git@github.com:zenml-io",
+            "must name an owner and a repository",
+        ),
+        (
+            "git@github.com:zenml-io/zenml#src",
+            "only point into the repository at a ref",
+        ),
+    ] {
+        let message = invalid
+            .parse::<SourceReference>()
+            .expect_err("Reference should be rejected")
+            .to_string();
+        assert!(
+            message.contains(reason),
+            "Expected `{reason}` for `{invalid}`: {message}"
+        );
+    }
+
+    for unpinned in ["git@github.com:zenml-io/zenml.git", "/srv/git/zenml"] {
+        let unpinned_pod = get_test_pod_builder()?
+            .source_commit_url(unpinned)
+            .build()?;
+        assert!(
+            !unpinned_pod.source_reference()?.is_pinned(),
+            "`{unpinned}` names no commit so it can't be pinned."
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn verify_equivalent_source_urls_hash_identically() -> Result<()> {
+    let pod = get_test_pod()?;
+    for spelling in [
+        "http://www.github.com/zenml-io/zenml.git/tree/0.67.0/",
+        "https://GitHub.com/zenml-io/zenml/blob/0.67.0",
+        "git@github.com:zenml-io/zenml.git@0.67.0",
+    ] {
+        let respelled_pod = get_test_pod_builder()?
+            .source_commit_url(spelling)
+            .build()?;
+        assert_eq!(
+            respelled_pod.hash, pod.hash,
+            "`{spelling}` changed the hash"
+        );
+        assert_eq!(to_yaml(&respelled_pod)?, to_yaml(&pod)?);
+    }
+
+    let commit_pod = get_test_pod_builder()?
+        .source_commit_url(
+            "https://github.com/zenml-io/zenml/commit/8a3a5ba8f4c5d3e2b1a0f9e8d7c6b5a4f3e2d1c0",
+        )
+        .build()?;
+    assert!(
+        commit_pod.source_reference()?.is_pinned(),
+        "Commit SHA should pin the source."
+    );
+    assert_ne!(commit_pod.hash, pod.hash);
+    Ok(())
+}
+
+#[test]
+fn verify_pod_command_templates_expand_to_stream_paths() -> Result<()> {
+    let pod = get_test_pod_builder()?
+        .command([
+            "python",
+            "style.py",
+            "--content={inputs.image}",
+            "--style={inputs.painting}",
+            "--out={outputs.styled}",
+            "--log-dir={output_dir}",
+            "{{output_dir}}",
+        ])
+        .build()?;
+    assert_eq!(
+        pod.command_argv()?,
+        vec![
+            "python",
+            "style.py",
+            "--content=/input/image.png",
+            "--style=/input/painting.png",
+            "--out=/output/styled.png",
+            "--log-dir=/output",
+            "{output_dir}",
+        ]
+    );
+    assert!(
+        to_yaml(&pod)?.contains("command:\n- python\n- style.py\n"),
+        "Argv should be written as a list."
+    );
+
+    let shell_pod = get_test_pod_builder()?
+        .command("cp {inputs.image} {outputs.styled} && echo done")
+        .output_dir("/my output")
+        .build()?;
+    assert_eq!(
+        shell_pod.command_argv()?,
+        vec![
+            "sh",
+            "-c",
+            "cp /input/image.png '/my output/styled.png' && echo done"
+        ]
+    );
+    assert_eq!(get_test_pod()?.command_argv()?[2], "tail -f /dev/null");
+    Ok(())
+}
+
+#[test]
+fn verify_pod_command_templates_are_validated() -> Result<()> {
+    let message = get_test_pod_builder()?
+        .command([
+            "python",
+            "--in={inputs.photo}",
+            "--out={outputs.mask}",
+            "{secrets.token}",
+        ])
+        .build()
+        .expect_err("Pod with placeholders for missing streams should not be built")
+        .to_string();
+    for expected in [
+        "Found 2 problems",
+        "`--in={inputs.photo}` can't be expanded: pod has no input stream `photo`",
+        "`--out={outputs.mask}` can't be expanded: pod has no output stream `mask`",
+    ] {
+        assert!(
+            message.contains(expected),
+            "Expected `{expected}` in error message: {message}"
+        );
+    }
+    assert!(
+        !message.contains("secrets.token"),
+        "Braces that aren't placeholders should be left alone: {message}"
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pod_command_keeps_other_braces() -> Result<()> {
+    for script in [
+        "echo ${HOME}",
+        "awk '{print $1}' f",
+        "find . -exec rm {} +",
+        "echo {{inputs",
+        "echo }{",
+    ] {
+        let pod = get_test_pod_builder()?.command(script).build()?;
+        assert_eq!(
+            pod.command_argv()?,
+            vec!["sh", "-c", script],
+            "Shell command `{script}` should run unchanged."
+        );
+    }
+
+    let pod = get_test_pod_builder()?
+        .command([
+            "awk",
+            "{print $1 > \"{output_dir}/first\"}",
+            "${inputs.image}",
+            "{{outputs.styled}}",
+        ])
+        .build()?;
+    assert_eq!(
+        pod.command_argv()?,
+        vec![
+            "awk",
+            "{print $1 > \"/output/first\"}",
+            "$/input/image.png",
+            "{outputs.styled}",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_diff_reports_nested_changes() -> Result<()> {
+    let pod = get_test_pod()?;
+    let mut changed_pod = get_test_pod_builder()?
+        .input_stream(
+            "painting",
+            StreamInfo {
+                path: PathBuf::from("/input/art.png"),
+                match_pattern: "/input/art.png".to_owned(),
+                ..StreamInfo::default()
+            },
+        )
+        .env("DEBUG", "1")
+        .secret_env("HF_TOKEN", SecretRef::new("hf-token"))
+        .required_gpu(GPURequirement {
+            vendor: GPUVendor::NVIDIA,
+            model: None,
+            min_memory: None,
+            min_compute_capability: None,
+            count: 1,
+        })
+        .build()?;
+    changed_pod.annotation = pod.annotation.clone().map(|annotation| Annotation {
+        version: Version::new(0, 68, 0),
+        ..annotation
+    });
+
+    let pod_diff = diff(&pod, &changed_pod)?;
+    assert_eq!(pod_diff.class, "pod");
+    assert_eq!(pod_diff.old_hash, pod.hash);
+    assert_eq!(pod_diff.new_hash, changed_pod.hash);
+    assert_eq!(
+        pod_diff
+            .hash_changes()
+            .map(|change| change.path.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "env",
+            "input_stream_map.painting.path",
+            "input_stream_map.painting.match_pattern",
+            "required_gpu",
+        ]
+    );
+    assert_eq!(
+        pod_diff
+            .annotation_changes()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec!["~ annotation.version: 0.67.0 -> 0.68.0"]
+    );
+    assert_eq!(
+        pod_diff
+            .secret_changes()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec![r#"+ secret_env: {"HF_TOKEN":"hf-token"}"#]
+    );
+    assert!(
+        pod_diff
+            .to_string()
+            .ends_with("Secret reference changes:\n  + secret_env: {\"HF_TOKEN\":\"hf-token\"}"),
+        "Unexpected diff: {pod_diff}"
+    );
+    assert!(
+        pod_diff
+            .to_string()
+            .contains("~ input_stream_map.painting.path: /input/painting.png -> /input/art.png"),
+        "Unexpected diff: {pod_diff}"
+    );
+    assert!(
+        diff(&pod, &get_test_pod()?)?.changes.is_empty(),
+        "Identical pods should not differ."
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pod_builder_matches_new() -> Result<()> {
+    assert_eq!(
+        get_test_pod()?,
+        Pod::new(
+            get_test_pod()?.annotation,
+            "https://github.com/zenml-io/zenml/tree/0.67.0".to_owned(),
+            "zenmldocker/zenml-server:0.67.0".to_owned(),
+            "tail -f /dev/null".into(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            BTreeMap::from([
+                (
+                    "painting".to_owned(),
+                    StreamInfo {
+                        path: PathBuf::from("/input/painting.png"),
+                        match_pattern: "/input/painting.png".to_owned(),
+                        ..StreamInfo::default()
+                    },
+                ),
+                (
+                    "image".to_owned(),
+                    StreamInfo {
+                        path: PathBuf::from("/input/image.png"),
+                        match_pattern: "/input/image.png".to_owned(),
+                        ..StreamInfo::default()
+                    },
+                ),
+            ]),
+            PathBuf::from("/output"),
+            BTreeMap::from([(
+                "styled".to_owned(),
+                StreamInfo {
+                    path: PathBuf::from("./styled.png"),
+                    match_pattern: "./styled.png".to_owned(),
+                    ..StreamInfo::default()
+                },
+            )]),
+            "250m".parse()?,
+            "2Gi".parse()?,
+            None,
+        )?
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pod_builder_requires_fields() {
+    let error = Pod::builder()
+        .image("zenmldocker/zenml-server:0.67.0")
+        .build()
+        .expect_err("Pod should not be built without a source commit URL.");
+    assert!(
+        error.to_string().contains("source_commit_url"),
+        "Unexpected error message: {error}"
+    );
+}
+
+#[test]
+fn verify_pod_env_is_hashed_but_secrets_are_not() -> Result<()> {
+    let pod = get_test_pod()?;
+    let pod_with_env = get_test_pod_builder()?.env("LOG_LEVEL", "debug").build()?;
+    let pod_with_secret = get_test_pod_builder()?
+        .secret_env("HF_TOKEN", SecretRef::new("hf-token"))
+        .build()?;
+
+    assert_ne!(pod_with_env.hash, pod.hash, "Env should change the hash.");
+    assert_eq!(
+        pod_with_secret.hash, pod.hash,
+        "Secret references should not change the hash."
+    );
+    assert_eq!(
+        to_yaml(&pod_with_secret)?,
+        to_yaml(&pod)?,
+        "Secret references should be left out of the spec."
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pod_env_validation() -> Result<()> {
+    let error = get_test_pod_builder()?
+        .env("1ST_RUN", "true")
+        .env("HF_TOKEN", "plain")
+        .secret_env("HF_TOKEN", SecretRef::new("../hf-token"))
+        .build()
+        .expect_err("Pod should reject the environment.");
+    let message = error.to_string();
+    assert!(
+        message.contains("Found 3 problems")
+            && message.contains("1ST_RUN")
+            && message.contains("set both directly and from a secret")
+            && message.contains("../hf-token"),
+        "Unexpected error message: {message}"
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pod_resolve_env() -> Result<()> {
+    let pod = get_test_pod_builder()?
+        .env("LOG_LEVEL", "debug")
+        .secret_env("HF_TOKEN", SecretRef::new("hf-token"))
+        .build()?;
+
+    let secret_directory = tempfile::tempdir()?;
+    fs::write(secret_directory.path().join("hf-token"), "hf_file\n")?;
+    assert_eq!(
+        pod.resolve_env(&FileSecretProvider::new(secret_directory.path()))?,
+        BTreeMap::from([
+            ("HF_TOKEN".to_owned(), "hf_file".to_owned()),
+            ("LOG_LEVEL".to_owned(), "debug".to_owned()),
+        ])
+    );
+
+    let provider = EnvSecretProvider::new("ORCA_TEST_RESOLVE_ENV_");
+    let message = pod
+        .resolve_env(&provider)
+        .expect_err("Secret should be missing from the environment.")
+        .to_string();
+    assert!(
+        message.contains("ORCA_TEST_RESOLVE_ENV_HF_TOKEN"),
+        "Unexpected error message: {message}"
+    );
+    std::env::set_var(
+        provider.variable_name(&SecretRef::new("hf-token")),
+        "hf_env",
+    );
+    assert_eq!(pod.resolve_env(&provider)?["HF_TOKEN"], "hf_env");
+    Ok(())
+}
+
+#[test]
+fn verify_pod_validation_collects_problems() {
+    let error = Pod::builder()
+        .source_commit_url("https://github.com/zenml-io/zenml/tree/0.67.0")
+        .image("")
+        .command("tail -f /dev/null")
+        .input_stream(
+            "image",
+            StreamInfo {
+                path: PathBuf::from("/input/image.png"),
+                match_pattern: "/input/[image.png".to_owned(),
+                ..StreamInfo::default()
+            },
+        )
+        .output_dir("/output")
+        .output_stream(
+            "styled",
+            StreamInfo {
+                path: PathBuf::from("../styled.png"),
+                match_pattern: "*.png".to_owned(),
+                ..StreamInfo::default()
+            },
+        )
+        .output_stream(
+            "first",
+            StreamInfo {
+                path: PathBuf::from("./result.png"),
+                match_pattern: "*.png".to_owned(),
+                ..StreamInfo::default()
+            },
+        )
+        .output_stream(
+            "second",
+            StreamInfo {
+                path: PathBuf::from("/output/result.png"),
+                match_pattern: "*.png".to_owned(),
+                ..StreamInfo::default()
+            },
+        )
+        .recommended_cpus(Cpu::ZERO)
+        .recommended_memory(Memory::ZERO)
+        .build()
+        .expect_err("Pod with invalid fields should not be built.");
+    let message = error.to_string();
+    for expected in [
+        "Found 6 problems",
+        "Field `image` of pod must not be empty",
+        "`recommended_cpus` must be greater than zero but got `0`",
+        "`recommended_memory` must be greater than zero but got `0`",
+        "Match pattern of stream `image` is not a valid glob",
+        "Output stream `styled` path `../styled.png` is outside of `output_dir`",
+        "Output streams [first, second] share the same path `/output/result.png`",
+    ] {
+        assert!(
+            message.contains(expected),
+            "Expected `{expected}` in error message: {message}"
+        );
+    }
+}
+
 #[test]
 fn verify_pod_to_yaml() -> Result<()> {
     assert_eq!(
         to_yaml::<Pod>(&get_test_pod()?)?,
         indoc! {"
             class: pod
+            schema: 3
             command: tail -f /dev/null
             image: zenmldocker/zenml-server:0.67.0
             input_stream_map:
@@ -35,11 +867,675 @@ fn verify_pod_to_yaml() -> Result<()> {
               styled:
                 path: ./styled.png
                 match_pattern: ./styled.png
-            recommended_cpus: 0.25
-            recommended_memory: 2147483648
+            recommended_cpus: 250m
+            recommended_memory: 2Gi
             required_gpu: null
             source_commit_url: https://github.com/zenml-io/zenml/tree/0.67.0
         "}
     );
     Ok(())
 }
+
+#[test]
+fn verify_pod_job_hash() -> Result<()> {
+    assert_eq!(
+        get_test_pod_job()?.hash,
+        "sha256:130bc3b55f8ac8bc0584f1e7f7d1a6c5e97d8659b4ba96d9b415200282697979"
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pod_job_to_yaml() -> Result<()> {
+    assert_eq!(
+        to_yaml::<PodJob>(&get_test_pod_job()?)?,
+        indoc! {"
+            class: podjob
+            schema: 3
+            cpu_limit: 500m
+            input_stream_map:
+              image: !Blob
+                kind: File
+                location: /data/images/cat.png
+                checksum: 4f2d8e9a1b7c6d5e
+              painting: !Blob
+                kind: File
+                location: /data/paintings/starry-night.png
+                checksum: 8a6e5f1c0c2b3d4e
+            memory_limit: 1Gi
+            pod_hash: sha256:6f9a810dd556dea713a8649096ddb58c416830bc57ddd42387c0d2d8268d0d3f
+        "}
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pod_job_rejects_unbound_streams() -> Result<()> {
+    let pod = get_test_pod()?;
+    let error = PodJob::new(
+        None,
+        &pod,
+        BTreeMap::from([(
+            "photo".to_owned(),
+            Input::Collection(vec![Blob {
+                kind: BlobKind::Directory,
+                location: PathBuf::from("/data/photos"),
+                checksum: "0d9f8e7c6b5a4f3e".to_owned(),
+            }]),
+        )]),
+        "500m".parse()?,
+        "1Gi".parse()?,
+    )
+    .expect_err("Pod job should not be created with mismatched input streams.");
+    let message = error.to_string();
+    assert!(
+        message.contains("Missing: [image, painting]") && message.contains("Unexpected: [photo]"),
+        "Unexpected error message: {message}"
+    );
+    Ok(())
+}
+
+fn typed_stream(media_type: &str, cardinality: Cardinality, optional: bool) -> StreamInfo {
+    StreamInfo {
+        path: PathBuf::from("/input/typed"),
+        match_pattern: "/input/typed/*".to_owned(),
+        media_type: Some(media_type.to_owned()),
+        cardinality,
+        optional,
+    }
+}
+
+fn file_blob(location: &str) -> Blob {
+    Blob {
+        kind: BlobKind::File,
+        location: PathBuf::from(location),
+        checksum: "0d9f8e7c6b5a4f3e".to_owned(),
+    }
+}
+
+#[test]
+fn verify_pod_job_checks_typed_inputs() -> Result<()> {
+    let pod = get_test_pod_builder()?
+        .input_stream("image", typed_stream("image/png", Cardinality::One, false))
+        .input_stream(
+            "painting",
+            typed_stream("inode/directory", Cardinality::ZeroOrMore, false),
+        )
+        .input_stream(
+            "mask",
+            typed_stream("image/*", Cardinality::OneOrMore, true),
+        )
+        .build()?;
+
+    let message = PodJob::new(
+        None,
+        &pod,
+        BTreeMap::from([
+            (
+                "image".to_owned(),
+                Input::Collection(vec![file_blob("/data/a.png"), file_blob("/data/b.png")]),
+            ),
+            (
+                "painting".to_owned(),
+                Input::Blob(file_blob("/data/painting.png")),
+            ),
+        ]),
+        "500m".parse()?,
+        "1Gi".parse()?,
+    )
+    .expect_err("Pod job should not be created with mistyped inputs.")
+    .to_string();
+    assert!(
+        message.contains("Found 2 problems")
+            && message.contains("expects exactly one blob(s) but got 2")
+            && message.contains("of media type `inode/directory`"),
+        "Unexpected error message: {message}"
+    );
+
+    PodJob::new(
+        None,
+        &pod,
+        BTreeMap::from([
+            ("image".to_owned(), Input::Blob(file_blob("/data/a.png"))),
+            ("painting".to_owned(), Input::Collection(vec![])),
+        ]),
+        "500m".parse()?,
+        "1Gi".parse()?,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn verify_pod_result_checks_typed_outputs() -> Result<()> {
+    let pod = get_test_pod_builder()?
+        .output_stream(
+            "styled",
+            StreamInfo {
+                path: PathBuf::from("./styled.png"),
+                ..typed_stream("image/png", Cardinality::One, false)
+            },
+        )
+        .output_stream(
+            "log",
+            StreamInfo {
+                path: PathBuf::from("./log.txt"),
+                ..typed_stream("text/plain", Cardinality::One, true)
+            },
+        )
+        .build()?;
+    let pod_job = PodJob::new(
+        None,
+        &pod,
+        BTreeMap::from([
+            ("image".to_owned(), Input::Blob(file_blob("/data/a.png"))),
+            ("painting".to_owned(), Input::Blob(file_blob("/data/b.png"))),
+        ]),
+        "500m".parse()?,
+        "1Gi".parse()?,
+    )?;
+
+    let result = |blobs: Vec<Blob>| {
+        PodResult::new(
+            None,
+            &pod,
+            &pod_job,
+            Status::Completed,
+            Some(0),
+            1_727_740_800,
+            1_727_740_862,
+            BTreeMap::from([("styled".to_owned(), blobs)]),
+        )
+    };
+    let message = result(vec![])
+        .expect_err("Pod result should not be missing a required output.")
+        .to_string();
+    assert!(
+        message.contains("expects exactly one blob(s) but got 0"),
+        "Unexpected error message: {message}"
+    );
+    result(vec![file_blob("/data/results/styled.png")])?;
+    Ok(())
+}
+
+#[test]
+fn verify_pod_result_hash() -> Result<()> {
+    assert_eq!(
+        get_test_pod_result()?.hash,
+        "sha256:7a6339c659f2b7d88f02e883e512fb0503fd8ff74fd4a557b236707bf67e0ba3"
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pod_result_to_yaml() -> Result<()> {
+    assert_eq!(
+        to_yaml::<PodResult>(&get_test_pod_result()?)?,
+        indoc! {"
+            class: podresult
+            schema: 3
+            end_time: 1727740862
+            exit_code: 0
+            output_stream_map:
+              styled:
+              - kind: File
+                location: /data/results/styled.png
+                checksum: c3b2a1f0e9d8c7b6
+            pod_job_hash: sha256:130bc3b55f8ac8bc0584f1e7f7d1a6c5e97d8659b4ba96d9b415200282697979
+            start_time: 1727740800
+            status: Completed
+        "}
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pod_result_records_failed_runs() -> Result<()> {
+    let pod = get_test_pod()?;
+    let pod_job = get_test_pod_job()?;
+    let result = |status: Status, end_time: u64| {
+        PodResult::new(
+            None,
+            &pod,
+            &pod_job,
+            status,
+            Some(1),
+            1_727_740_800,
+            end_time,
+            BTreeMap::new(),
+        )
+    };
+    result(Status::Failed, 1_727_740_801)?;
+    result(Status::Killed, 1_727_740_801)?;
+    assert!(
+        result(Status::Completed, 1_727_740_801).is_err(),
+        "Completed runs should have every required output"
+    );
+
+    let message = result(Status::Failed, 1_727_740_799)
+        .expect_err("Pod result should not end before it starts.")
+        .to_string();
+    assert!(
+        message.contains("before it starts"),
+        "Unexpected error message: {message}"
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pod_result_rejects_foreign_job() -> Result<()> {
+    let mut other_pod = get_test_pod()?;
+    other_pod.hash = format!("sha256:{}", "0".repeat(64));
+    let error = PodResult::new(
+        None,
+        &other_pod,
+        &get_test_pod_job()?,
+        Status::Killed,
+        None,
+        1_727_740_800,
+        1_727_740_801,
+        BTreeMap::new(),
+    )
+    .expect_err("Pod result should not be created for a job of another pod.");
+    assert!(
+        error.to_string().contains("Pod job references pod"),
+        "Unexpected error message: {error}"
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pipeline_hash() -> Result<()> {
+    let pod = get_test_pod()?;
+    assert_eq!(
+        get_test_pipeline(&pod)?.hash,
+        "sha256:176bc88bad640d7bd8c1c953e62b7aa3a155fe0daf7df6b9eec8ed4a309587ea"
+    );
+
+    let mut changed_pod = pod;
+    changed_pod.hash = format!("sha256:{}", "0".repeat(64));
+    assert_ne!(
+        get_test_pipeline(&changed_pod)?.hash,
+        get_test_pipeline(&get_test_pod()?)?.hash,
+        "Changing a member pod should change the pipeline hash."
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pipeline_to_yaml() -> Result<()> {
+    assert_eq!(
+        to_yaml::<Pipeline>(&get_test_pipeline(&get_test_pod()?)?)?,
+        indoc! {"
+            class: pipeline
+            schema: 3
+            edges:
+            - source:
+                node: first
+                stream: styled
+              target:
+                node: second
+                stream: image
+            input_stream_map:
+              image:
+              - node: first
+                stream: image
+              painting:
+              - node: first
+                stream: painting
+              - node: second
+                stream: painting
+            nodes:
+              first: sha256:6f9a810dd556dea713a8649096ddb58c416830bc57ddd42387c0d2d8268d0d3f
+              second: sha256:6f9a810dd556dea713a8649096ddb58c416830bc57ddd42387c0d2d8268d0d3f
+            output_stream_map:
+              styled:
+                node: second
+                stream: styled
+        "}
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pipeline_rejects_incompatible_edge() -> Result<()> {
+    let pipeline = |styled: StreamInfo, image: StreamInfo| -> Result<String> {
+        let pod = get_test_pod_builder()?
+            .input_stream("image", image)
+            .output_stream(
+                "styled",
+                StreamInfo {
+                    path: PathBuf::from("./styled.png"),
+                    ..styled
+                },
+            )
+            .build()?;
+        Ok(get_test_pipeline(&pod)
+            .expect_err("Pipeline should have been rejected.")
+            .to_string())
+    };
+
+    let message = pipeline(
+        typed_stream("image/png", Cardinality::One, false),
+        typed_stream("image/jpeg", Cardinality::One, false),
+    )?;
+    assert!(
+        message.contains("Stream `first.styled` can't feed `second.image`")
+            && message.contains("media type `image/png` doesn't match `image/jpeg`"),
+        "Unexpected error message: {message}"
+    );
+
+    let message = pipeline(
+        typed_stream("image/png", Cardinality::OneOrMore, false),
+        typed_stream("image/*", Cardinality::One, false),
+    )?;
+    assert!(
+        message.contains("one or more blob(s) produced where exactly one accepted"),
+        "Unexpected error message: {message}"
+    );
+
+    let message = pipeline(
+        typed_stream("image/png", Cardinality::One, true),
+        typed_stream("image/png", Cardinality::ZeroOrMore, false),
+    )?;
+    assert!(
+        message.contains("an optional output feeds a required input"),
+        "Unexpected error message: {message}"
+    );
+    Ok(())
+}
+
+fn pipeline_error(
+    edges: Vec<Edge>,
+    input_stream_map: BTreeMap<String, BTreeSet<StreamRef>>,
+) -> Result<String> {
+    let pod = get_test_pod()?;
+    Ok(Pipeline::new(
+        None,
+        &BTreeMap::from([
+            ("first".to_owned(), pod.clone()),
+            ("second".to_owned(), pod),
+        ]),
+        edges,
+        input_stream_map,
+        BTreeMap::new(),
+    )
+    .expect_err("Pipeline should have been rejected.")
+    .to_string())
+}
+
+#[test]
+fn verify_pod_to_json() -> Result<()> {
+    assert_eq!(
+        to_json(&get_test_pod()?)?,
+        indoc! {r#"
+            {
+              "class": "pod",
+              "schema": 3,
+              "command": "tail -f /dev/null",
+              "image": "zenmldocker/zenml-server:0.67.0",
+              "input_stream_map": {
+                "image": {
+                  "match_pattern": "/input/image.png",
+                  "path": "/input/image.png"
+                },
+                "painting": {
+                  "match_pattern": "/input/painting.png",
+                  "path": "/input/painting.png"
+                }
+              },
+              "output_dir": "/output",
+              "output_stream_map": {
+                "styled": {
+                  "match_pattern": "./styled.png",
+                  "path": "./styled.png"
+                }
+              },
+              "recommended_cpus": "250m",
+              "recommended_memory": "2Gi",
+              "required_gpu": null,
+              "source_commit_url": "https://github.com/zenml-io/zenml/tree/0.67.0"
+            }
+        "#},
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pod_to_toml() -> Result<()> {
+    assert_eq!(
+        to_toml(&get_test_pod()?)?,
+        indoc! {r#"
+            class = "pod"
+            schema = 3
+            command = "tail -f /dev/null"
+            image = "zenmldocker/zenml-server:0.67.0"
+            output_dir = "/output"
+            recommended_cpus = "250m"
+            recommended_memory = "2Gi"
+            source_commit_url = "https://github.com/zenml-io/zenml/tree/0.67.0"
+
+            [input_stream_map.image]
+            match_pattern = "/input/image.png"
+            path = "/input/image.png"
+
+            [input_stream_map.painting]
+            match_pattern = "/input/painting.png"
+            path = "/input/painting.png"
+
+            [output_stream_map.styled]
+            match_pattern = "./styled.png"
+            path = "./styled.png"
+        "#},
+    );
+    Ok(())
+}
+
+fn assert_formats_round_trip<T>(model: &T, hash: &str) -> Result<()>
+where
+    T: Serialize + serde::de::DeserializeOwned,
+{
+    for format in SpecFormat::ALL {
+        let loaded = from_spec::<T>(&to_spec(model, format)?, format, hash, None)?;
+        assert_eq!(
+            to_canonical(&loaded)?,
+            to_canonical(model)?,
+            "{} spec should hash the same as the original.",
+            format.extension()
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn verify_formats_round_trip_to_same_hash() -> Result<()> {
+    let pod = get_test_pod()?;
+    assert_formats_round_trip(&pod, &pod.hash)?;
+    let pod_job = get_test_pod_job()?;
+    assert_formats_round_trip(&pod_job, &pod_job.hash)?;
+    let pod_result = get_test_pod_result()?;
+    assert_formats_round_trip(&pod_result, &pod_result.hash)?;
+    let pipeline = get_test_pipeline(&pod)?;
+    assert_formats_round_trip(&pipeline, &pipeline.hash)
+}
+
+#[test]
+fn verify_from_yaml_upgrades_unversioned_spec() -> Result<()> {
+    let pod = get_test_pod()?;
+    let legacy_yaml = to_yaml(&pod)?
+        .replace("schema: 3\n", "")
+        .replace("recommended_cpus: 250m", "recommended_cpus: 0.25")
+        .replace("recommended_memory: 2Gi", "recommended_memory: 2147483648");
+    let loaded_pod = from_yaml::<Pod>(&legacy_yaml, &pod.hash, None)?;
+    assert_eq!(
+        loaded_pod.hash, pod.hash,
+        "Upgrading should keep the original hash."
+    );
+    assert_eq!(to_yaml(&loaded_pod)?, to_yaml(&pod)?);
+
+    let future_yaml = to_yaml(&pod)?.replace("schema: 3\n", "schema: 4\n");
+    let message = from_yaml::<Pod>(&future_yaml, &pod.hash, None)
+        .expect_err("Spec from a newer release should be rejected")
+        .to_string();
+    assert!(
+        message.contains("Schema version `4` of pod spec is not supported"),
+        "Unexpected error message: {message}"
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_from_yaml_upgrades_legacy_gpu_requirement() -> Result<()> {
+    let pod = get_test_pod_builder()?
+        .required_gpu(GPURequirement {
+            vendor: GPUVendor::NVIDIA,
+            model: Some("A100".to_owned()),
+            min_memory: Some("40Gi".parse()?),
+            min_compute_capability: None,
+            count: 2,
+        })
+        .build()?;
+    let legacy_yaml = to_yaml(&pod)?
+        .replace("schema: 3\n", "schema: 2\n")
+        .replace(
+            indoc! {"
+            required_gpu:
+              vendor: NVIDIA
+              model: A100
+              min_memory: 40Gi
+              count: 2
+        "},
+            indoc! {"
+            required_gpu:
+              model: !NVIDIA A100
+              recommended_memory: 42949672960
+              count: 2
+        "},
+        );
+    assert!(
+        legacy_yaml.contains("!NVIDIA"),
+        "Fixture should contain the legacy layout: {legacy_yaml}"
+    );
+    let loaded_pod = from_yaml::<Pod>(&legacy_yaml, &pod.hash, None)?;
+
+    assert_eq!(to_yaml(&loaded_pod)?, to_yaml(&pod)?);
+
+    let mut legacy_json = serde_json::from_str::<serde_json::Value>(&to_json(&pod)?)?;
+    legacy_json["schema"] = 2.into();
+    legacy_json["required_gpu"] = serde_json::json!({
+        "model": {"NVIDIA": "A100"},
+        "recommended_memory": 42_949_672_960_u64,
+        "count": 2,
+    });
+    let loaded_pod = from_spec::<Pod>(&legacy_json.to_string(), SpecFormat::Json, &pod.hash, None)?;
+    assert_eq!(to_yaml(&loaded_pod)?, to_yaml(&pod)?);
+    Ok(())
+}
+
+#[test]
+fn verify_migrate_applies_steps_for_class() -> Result<()> {
+    let migrations = [
+        Migration {
+            class: "pod",
+            from: 0,
+            apply: |spec| {
+                let command = spec.remove("cmd").unwrap_or(Value::Null);
+                spec.insert("command".into(), command);
+                Ok(())
+            },
+        },
+        Migration {
+            class: "podjob",
+            from: 0,
+            apply: |_| {
+                Err(OrcaError::from(io::Error::other(
+                    "applied to the wrong class",
+                )))
+            },
+        },
+    ];
+    let mut spec: Mapping = serde_yaml::from_str(indoc! {"
+        class: pod
+        cmd: tail -f /dev/null
+    "})?;
+
+    assert!(
+        migrate::<Pod>(&mut spec, &migrations)?,
+        "Spec should be upgraded."
+    );
+    assert_eq!(
+        spec,
+        serde_yaml::from_str::<Mapping>(indoc! {"
+            class: pod
+            command: tail -f /dev/null
+            schema: 3
+        "})?
+    );
+    assert!(
+        !migrate::<Pod>(&mut spec, &migrations)?,
+        "Current spec should be left as is."
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pipeline_rejects_cycle() -> Result<()> {
+    let message = pipeline_error(
+        vec![
+            Edge {
+                source: stream_ref("first", "styled"),
+                target: stream_ref("second", "image"),
+            },
+            Edge {
+                source: stream_ref("second", "styled"),
+                target: stream_ref("first", "image"),
+            },
+        ],
+        BTreeMap::from([(
+            "painting".to_owned(),
+            BTreeSet::from([
+                stream_ref("first", "painting"),
+                stream_ref("second", "painting"),
+            ]),
+        )]),
+    )?;
+    assert!(
+        message.contains("cycle") && message.contains("first") && message.contains("second"),
+        "Unexpected error message: {message}"
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pipeline_rejects_dangling_stream_key() -> Result<()> {
+    let message = pipeline_error(
+        vec![Edge {
+            source: stream_ref("first", "stylized"),
+            target: stream_ref("second", "image"),
+        }],
+        BTreeMap::new(),
+    )?;
+    assert!(
+        message.contains("has no stream") && message.contains("stylized"),
+        "Unexpected error message: {message}"
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_pipeline_rejects_unconnected_input() -> Result<()> {
+    let message = pipeline_error(
+        vec![Edge {
+            source: stream_ref("first", "styled"),
+            target: stream_ref("second", "image"),
+        }],
+        BTreeMap::from([(
+            "image".to_owned(),
+            BTreeSet::from([stream_ref("first", "image")]),
+        )]),
+    )?;
+    assert!(
+        message.contains("is not connected") && message.contains("painting"),
+        "Unexpected error message: {message}"
+    );
+    Ok(())
+}
@@ -1,37 +1,850 @@
 #![expect(clippy::panic_in_result_fn, reason = "Panics OK in tests.")]
 
 pub mod fixture;
-use anyhow::Result;
-use fixture::{get_test_item, store_test, ModelType};
-use orcapod::store::ModelID;
-use std::fs;
+use anyhow::{anyhow, Result};
+use fixture::{
+    get_test_pipeline, get_test_pod, get_test_pod_builder, get_test_pod_job, get_test_pod_job_of,
+    get_test_pod_result, get_test_pod_result_of, renamed, store_test, store_test_with,
+    TestLocalStore,
+};
+use indoc::indoc;
+use orcapod::{
+    model::{
+        to_canonical, to_json, to_toml, to_yaml, Annotation, HashAlgorithm, Model, Pipeline, Pod,
+        Version, VersionReq,
+    },
+    secrets::SecretRef,
+    store::{filestore::LocalFileStore, LabelSelector, ModelID, PinPolicy, Store},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+    thread,
+};
 use tempfile::tempdir;
 
 #[test]
 fn test_pod_with_file_store() -> Result<()> {
-    test_item_store_with_annotation(&ModelType::Pod)
+    test_item_store_with_annotation(&get_test_pod()?)
+}
+
+#[test]
+fn test_pod_job_with_file_store() -> Result<()> {
+    test_item_store_with_annotation(&get_test_pod_job()?)
+}
+
+#[test]
+fn test_pod_result_with_file_store() -> Result<()> {
+    test_item_store_with_annotation(&get_test_pod_result()?)
+}
+
+#[test]
+fn test_pipeline_with_file_store() -> Result<()> {
+    test_item_store_with_annotation(&get_test_pipeline(&get_test_pod()?)?)
+}
+
+fn save_pod_versions(store: &TestLocalStore, versions: &[&str]) -> Result<()> {
+    let mut pod = get_test_pod()?;
+    for version in versions {
+        let version = Version::parse(version)?;
+        pod.annotation = pod.annotation.map(|annotation| Annotation {
+            version,
+            ..annotation
+        });
+        store.save_pod(&pod)?;
+    }
+    Ok(())
+}
+
+fn loaded_version(store: &TestLocalStore, model_id: &ModelID) -> Result<String> {
+    Ok(store
+        .load_pod(model_id)?
+        .annotation
+        .map(|annotation| annotation.version.to_string())
+        .unwrap_or_default())
+}
+
+#[test]
+fn test_list_pod_orders_versions_semantically() -> Result<()> {
+    let store = store_test(None)?;
+    save_pod_versions(&store, &["0.10.0", "0.9.0", "0.10.0-rc.1", "1.0.0+build.5"])?;
+
+    assert_eq!(
+        store
+            .list_pod()?
+            .into_iter()
+            .map(|model_info| model_info.version.to_string())
+            .collect::<Vec<_>>(),
+        vec!["0.9.0", "0.10.0-rc.1", "0.10.0", "1.0.0+build.5"],
+        "Versions should be listed in semantic order"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_name_ver_index_follows_saves_and_deletes() -> Result<()> {
+    let store = store_test(None)?;
+    save_pod_versions(&store, &["0.9.0", "0.10.0"])?;
+    let index_path = store.get_directory().join("index/pod");
+    let indexed_files = |index_path: &Path| -> Result<String> {
+        let mut file_names = String::new();
+        for name_entry in fs::read_dir(index_path)? {
+            for entry in fs::read_dir(name_entry?.path())? {
+                file_names.push_str(&entry?.file_name().to_string_lossy());
+                file_names.push('\n');
+            }
+        }
+        Ok(file_names)
+    };
+    assert!(
+        indexed_files(&index_path)?.contains("0.10.0"),
+        "Saving should record the annotation in the index"
+    );
+
+    store.delete_annotation::<Pod>("style-transfer", &Version::new(0, 9, 0))?;
+    let listed_versions = |store: &TestLocalStore| -> Result<Vec<String>> {
+        Ok(store
+            .list_pod()?
+            .into_iter()
+            .map(|model_info| model_info.version.to_string())
+            .collect())
+    };
+    assert_eq!(listed_versions(&store)?, vec!["0.10.0"]);
+
+    fs::remove_dir_all(&index_path)?;
+    assert_eq!(
+        listed_versions(&store)?,
+        vec!["0.10.0"],
+        "A missing index should be rebuilt from the annotation files"
+    );
+    assert!(index_path.exists(), "Rebuilt index should be written back");
+
+    // Looking up one version only reads the entries of that name
+    fs::create_dir_all(index_path.join("other"))?;
+    fs::write(index_path.join("other/not-a-version.yaml"), "")?;
+    assert_eq!(
+        store
+            .load_pod(&ModelID::Latest("style-transfer".to_owned()))?
+            .annotation
+            .map(|annotation| annotation.version),
+        Some(Version::new(0, 10, 0))
+    );
+    fs::remove_dir_all(index_path.join("other"))?;
+
+    store.delete_pod(&ModelID::Latest("style-transfer".to_owned()))?;
+    assert!(
+        indexed_files(&index_path)?.is_empty(),
+        "Deleting the pod should drop its annotations from the index"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_concurrent_saves_from_two_stores_keep_every_index_entry() -> Result<()> {
+    let store = store_test(None)?;
+    let other_store = LocalFileStore::new(store.get_directory());
+    let pod = get_test_pod()?;
+    let save_versions = |store: &LocalFileStore, minor: u64| -> Result<()> {
+        for patch in 0..20 {
+            let mut versioned_pod = pod.clone();
+            versioned_pod.annotation = versioned_pod.annotation.map(|annotation| Annotation {
+                version: Version::new(1, minor, patch),
+                ..annotation
+            });
+            store.save_pod(&versioned_pod)?;
+        }
+        Ok(())
+    };
+    thread::scope(|scope| -> Result<()> {
+        let saving = scope.spawn(|| save_versions(&other_store, 1));
+        save_versions(&store, 0)?;
+        saving
+            .join()
+            .map_err(|_| anyhow!("Saving from the other store panicked"))?
+    })?;
+
+    assert_eq!(
+        LocalFileStore::new(store.get_directory()).list_pod()?.len(),
+        40,
+        "Saves from both stores should be in the index"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_annotation_names_round_trip_through_file_names() -> Result<()> {
+    let store = store_test(None)?;
+    let pod = get_test_pod()?;
+    let names_versions = [
+        ("vision/style.transfer_v2", "1.0.0"),
+        ("画像 スタイル", "0.1.0"),
+        ("50% off", "0.1.0"),
+        ("release-1", "1.0.0-1.0.0"),
+        ("release-1.0.0", "1.0.0"),
+        ("Style", "1.0.0"),
+        ("style", "1.0.0"),
+        ("style", "1.0.0-RC.1"),
+        ("style", "1.0.0-rc.1"),
+    ];
+    for (name, version) in names_versions {
+        let parsed_version = Version::parse(version)?;
+        let mut named_pod = pod.clone();
+        named_pod.annotation = named_pod.annotation.map(|annotation| Annotation {
+            name: name.to_owned(),
+            version: parsed_version,
+            ..annotation
+        });
+        store.save_pod(&named_pod)?;
+    }
+
+    let mut sorted_names_versions = names_versions
+        .iter()
+        .map(|(name, version)| Ok(((*name).to_owned(), Version::parse(version)?)))
+        .collect::<Result<Vec<_>>>()?;
+    sorted_names_versions.sort();
+    let expected = sorted_names_versions
+        .into_iter()
+        .map(|(name, version)| (name, version.to_string()))
+        .collect::<Vec<_>>();
+    let listed = |store: &TestLocalStore| -> Result<Vec<(String, String)>> {
+        Ok(store
+            .list_pod()?
+            .into_iter()
+            .map(|model_info| (model_info.name, model_info.version.to_string()))
+            .collect())
+    };
+    assert_eq!(listed(&store)?, expected);
+    store.rebuild_index::<Pod>()?;
+    assert_eq!(
+        listed(&store)?,
+        expected,
+        "Names should be decoded from the annotation file names"
+    );
+    assert_eq!(
+        store
+            .load_pod(&ModelID::Latest("release-1".to_owned()))?
+            .annotation
+            .map(|annotation| annotation.version.to_string()),
+        Some("1.0.0-1.0.0".to_owned())
+    );
+
+    let mut case_folded_file_names = BTreeSet::new();
+    for entry in fs::read_dir(store.make_path::<Pod>(&pod.hash, "annotations"))? {
+        assert!(
+            case_folded_file_names.insert(entry?.file_name().to_string_lossy().to_lowercase()),
+            "Annotations differing only in case should not share a file on case-insensitive file \
+             systems"
+        );
+    }
+    assert_eq!(case_folded_file_names.len(), names_versions.len());
+    Ok(())
+}
+
+#[test]
+fn test_save_pod_rejects_invalid_annotation_names() -> Result<()> {
+    let store = store_test(None)?;
+    let pod = get_test_pod()?;
+    for (name, reason) in [
+        (String::new(), "it is empty"),
+        (" padded".to_owned(), "starts or ends with whitespace"),
+        ("tab\tname".to_owned(), "contains control characters"),
+        ("画".repeat(100), "too long to be stored as a file name"),
+    ] {
+        let mut named_pod = pod.clone();
+        named_pod.annotation = named_pod.annotation.map(|annotation| Annotation {
+            name: name.clone(),
+            ..annotation
+        });
+        let error = store
+            .save_pod(&named_pod)
+            .expect_err("Saving should fail on an invalid annotation name");
+        assert!(
+            error.to_string().contains(reason),
+            "Unexpected error for `{name}`: {error}"
+        );
+        assert!(
+            !store.make_path::<Pod>(&pod.hash, "").exists(),
+            "Nothing should be written for `{name}`"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_load_pod_by_latest_and_version_req() -> Result<()> {
+    let store = store_test(None)?;
+    save_pod_versions(
+        &store,
+        &["0.9.0", "0.10.0", "0.10.1", "1.2.0", "1.3.0-rc.1"],
+    )?;
+
+    assert_eq!(
+        loaded_version(&store, &ModelID::Latest("style-transfer".to_owned()))?,
+        "1.2.0",
+        "Latest should skip pre-releases when there is a release"
+    );
+    let pre_releases = [
+        Version::parse("2.0.0-alpha.1")?,
+        Version::parse("2.0.0-rc.1")?,
+    ];
+    assert_eq!(
+        ModelID::Latest("style-transfer".to_owned()).select_version(&pre_releases),
+        Some(&pre_releases[1]),
+        "Latest should fall back to the highest pre-release"
+    );
+    assert_eq!(
+        loaded_version(
+            &store,
+            &ModelID::VersionReq("style-transfer".to_owned(), VersionReq::parse("^0.10")?)
+        )?,
+        "0.10.1"
+    );
+    assert_eq!(
+        loaded_version(
+            &store,
+            &ModelID::VersionReq(
+                "style-transfer".to_owned(),
+                VersionReq::parse(">=0.9, <0.10")?
+            )
+        )?,
+        "0.9.0"
+    );
+    assert_eq!(
+        loaded_version(
+            &store,
+            &ModelID::VersionReq("style-transfer".to_owned(), VersionReq::parse("^1")?)
+        )?,
+        "1.2.0",
+        "Pre-releases should only match requirements that opt into them"
+    );
+
+    let error = store
+        .load_pod(&ModelID::VersionReq(
+            "style-transfer".to_owned(),
+            VersionReq::parse("^2")?,
+        ))
+        .expect_err("No stored version satisfies the requirement");
+    assert!(
+        error
+            .to_string()
+            .contains("No annotation found for `style-transfer:^2` pod"),
+        "Unexpected error message: {error}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_load_pod_by_hash_prefix() -> Result<()> {
+    let store = store_test(None)?;
+    let pod = get_test_pod()?;
+    store.save_pod(&pod)?;
+    let digest = pod.hash.trim_start_matches("sha256:");
+
+    for prefix in [&digest[..8], &pod.hash[..15]] {
+        assert_eq!(
+            store.load_pod(&ModelID::Hash(prefix.to_owned()))?.hash,
+            pod.hash,
+            "Prefix `{prefix}` should resolve to the pod"
+        );
+    }
+
+    // a second entry sharing the first 4 characters of the digest
+    let other_hash = format!("sha256:{}{}", &digest[..4], "0".repeat(60));
+    fs::create_dir_all(store.make_path::<Pod>(&other_hash, ""))?;
+
+    let ambiguous = store
+        .load_pod(&ModelID::Hash(digest[..4].to_owned()))
+        .expect_err("Prefix should be ambiguous");
+    assert!(
+        ambiguous.to_string().contains("is ambiguous")
+            && ambiguous.to_string().contains(&pod.hash)
+            && ambiguous.to_string().contains(&other_hash),
+        "Unexpected error message: {ambiguous}"
+    );
+
+    let no_match = store
+        .delete_pod(&ModelID::Hash("blake3:".to_owned() + &digest[..4]))
+        .expect_err("Prefix should not match hashes of another algorithm");
+    assert!(
+        no_match
+            .to_string()
+            .contains("No pod found with a hash starting with `blake3:"),
+        "Unexpected error message: {no_match}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_pipeline_with_generic_store_methods() -> Result<()> {
+    let store = store_test(None)?;
+    let pipeline = get_test_pipeline(&get_test_pod()?)?;
+    store.save(&pipeline)?;
+
+    let pipelines = store.list::<Pipeline>()?;
+    assert!(
+        pipelines.len() == 1
+            && pipelines[0].name == "double-style-transfer"
+            && pipelines[0].hash == pipeline.hash,
+        "Pipeline should be listed like the other models"
+    );
+    assert_eq!(
+        store.load::<Pipeline>(&ModelID::Latest("double-style-transfer".to_owned()))?,
+        pipeline
+    );
+    assert!(
+        store.list::<Pod>()?.is_empty(),
+        "Pipelines should be kept apart from pods"
+    );
+
+    store.delete::<Pipeline>(&ModelID::Hash(pipeline.hash.clone()))?;
+    assert!(
+        store.list::<Pipeline>()?.is_empty(),
+        "Pipeline should be deleted"
+    );
+    Ok(())
+}
+
+/// Model filed under a class name of its own rather than its type name.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Note {
+    annotation: Option<Annotation>,
+    hash: String,
+    text: String,
+}
+
+impl Model for Note {
+    fn class_name() -> String {
+        "memo".to_owned()
+    }
+
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn annotation(&self) -> Option<&Annotation> {
+        self.annotation.as_ref()
+    }
+}
+
+#[test]
+fn test_model_with_custom_class_name() -> Result<()> {
+    let store = store_test(None)?;
+    let pod = get_test_pod()?;
+    let note = Note {
+        annotation: pod.annotation.clone(),
+        hash: "placeholder".to_owned(),
+        text: "Stored under its class name.".to_owned(),
+    };
+    store.save(&note)?;
+
+    let mut directories = fs::read_dir(store.get_directory())?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>>>()?;
+    directories.sort();
+    assert_eq!(directories, ["index", "memo"]);
+
+    let notes = store.list::<Note>()?;
+    assert!(
+        notes.len() == 1 && notes[0].name == "style-transfer",
+        "Note should be listed from its index"
+    );
+    let loaded = store.load::<Note>(&ModelID::Latest("style-transfer".to_owned()))?;
+    assert_eq!(loaded.text, note.text);
+
+    store.delete::<Note>(&ModelID::Hash(notes[0].hash.clone()))?;
+    assert!(store.list::<Note>()?.is_empty(), "Note should be deleted");
+    Ok(())
+}
+
+#[test]
+fn test_models_with_blake3_file_store() -> Result<()> {
+    let store = store_test_with(|store| store.with_hash_algorithm(HashAlgorithm::Blake3))?;
+    let error = store
+        .save_pod(&get_test_pod()?)
+        .expect_err("A SHA-256 pod should not be saved in a BLAKE3 store");
+    assert!(
+        error
+            .to_string()
+            .contains("is hashed with another algorithm than the store keys models by"),
+        "Unexpected error message: {error}"
+    );
+
+    let pod = get_test_pod_builder()?
+        .hash_algorithm(HashAlgorithm::Blake3)
+        .build()?;
+    assert!(pod.hash.starts_with("blake3:"));
+    store.save_pod(&pod)?;
+    let items = store.list_pod()?;
+    assert!(
+        items.len() == 1 && items[0].hash == pod.hash,
+        "Pod should be keyed by its BLAKE3 hash"
+    );
+
+    // Models made from the pod reference it by a hash the store can resolve
+    let pod_job = get_test_pod_job_of(&pod)?;
+    let pipeline = get_test_pipeline(&pod)?;
+    assert!(pod_job.hash.starts_with("blake3:") && pipeline.hash.starts_with("blake3:"));
+    store.save(&pod_job)?;
+    store.save(&pipeline)?;
+    let pod_hash = serde_yaml::to_value(&pod_job)?["pod_hash"]
+        .as_str()
+        .unwrap_or_default()
+        .to_owned();
+    let loaded_pod = store.load_pod(&ModelID::Hash(pod_hash))?;
+    assert_eq!(loaded_pod.hash, pod.hash);
+    let pod_result = get_test_pod_result_of(
+        &loaded_pod,
+        &store.load(&ModelID::Hash(pod_job.hash.clone()))?,
+    )?;
+    assert!(pod_result.hash.starts_with("blake3:"));
+    store.save(&pod_result)?;
+
+    // A job made from the SHA-256 spelling of the same pod still belongs to it
+    get_test_pod_result_of(&loaded_pod, &get_test_pod_job()?)?;
+    Ok(())
+}
+
+/// Spec of the test pod as written by releases from before specs had a schema version. The
+/// earliest of them named its directory by the SHA-256 digest of this file.
+const UNVERSIONED_SPEC_YAML: &str = indoc! {"
+    class: pod
+    command: tail -f /dev/null
+    image: zenmldocker/zenml-server:0.67.0
+    input_stream_map:
+      image:
+        path: /input/image.png
+        match_pattern: /input/image.png
+      painting:
+        path: /input/painting.png
+        match_pattern: /input/painting.png
+    output_dir: /output
+    output_stream_map:
+      styled:
+        path: ./styled.png
+        match_pattern: ./styled.png
+    recommended_cpus: 0.25
+    recommended_memory: 2147483648
+    required_gpu: null
+    source_commit_url: https://github.com/zenml-io/zenml/tree/0.67.0
+"};
+
+const OLD_ANNOTATION_YAML: &str = indoc! {"
+    name: style-transfer
+    version: 0.67.0
+    description: This is an example pod.
+"};
+
+/// Write the spec and annotation of a pod the way an older release stored them under `hash`.
+fn write_old_pod(store: &LocalFileStore, hash: &str, spec_yaml: &str) -> Result<()> {
+    fs::create_dir_all(store.make_path::<Pod>(hash, "annotations"))?;
+    fs::write(store.make_path::<Pod>(hash, "spec.yaml"), spec_yaml)?;
+    fs::write(
+        store.make_path::<Pod>(hash, "annotations/style-transfer-0.67.0.yaml"),
+        OLD_ANNOTATION_YAML,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_load_pod_from_bare_hex_store() -> Result<()> {
+    let store = store_test(None)?;
+    let digest = "13d69656d396c272588dd875b2802faee1a56bd985e3c43c7db276a373bc9ddb";
+    write_old_pod(&store, digest, UNVERSIONED_SPEC_YAML)?;
+
+    let hash = format!("sha256:{digest}");
+    assert_eq!(
+        store.list_pod()?[0].hash,
+        hash,
+        "Bare hashes should be listed with their algorithm"
+    );
+    for model_id in [
+        ModelID::NameVer("style-transfer".to_owned(), Version::new(0, 67, 0)),
+        ModelID::Hash(hash.clone()),
+    ] {
+        let pod = store.load_pod(&model_id)?;
+        assert_eq!(pod.hash, hash);
+        assert_eq!(to_yaml(&pod)?, to_yaml(&get_test_pod()?)?);
+    }
+
+    // Hashed again with the current canonical form, the pod would get a second annotation file
+    let pod = store.load_pod(&ModelID::NameVer(
+        "style-transfer".to_owned(),
+        Version::new(0, 67, 0),
+    ))?;
+    assert!(
+        store
+            .save_pod(&pod)
+            .is_err_and(|error| error.to_string().contains("already taken")),
+        "Re-saving should not take over the annotation"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_save_pod_rejects_annotation_of_another_hash() -> Result<()> {
+    let store = store_test(None)?;
+    let pod = get_test_pod()?;
+    store.save_pod(&pod)?;
+    let other_pod = get_test_pod_builder()?
+        .image("zenmldocker/zenml-server:0.68.0")
+        .build()?;
+
+    assert!(store
+        .save_pod(&other_pod)
+        .is_err_and(|error| error.to_string().contains("already taken")));
+    assert!(
+        !store.make_annotation_path(&other_pod).exists(),
+        "A rejected save should not leave an annotation file"
+    );
+    assert_eq!(store.list_pod()?.len(), 1);
+    assert_eq!(
+        store
+            .load_pod(&ModelID::NameVer(
+                "style-transfer".to_owned(),
+                Version::new(0, 67, 0)
+            ))?
+            .hash,
+        pod.hash
+    );
+    Ok(())
+}
+
+#[test]
+fn test_load_pod_upgrades_unversioned_spec() -> Result<()> {
+    // Written by releases with prefixed hashes, before and after specs had a schema version
+    let hash = "sha256:fee2392f679ab826697964930b070373656e9de5118f0dcc88b1f1a595b90f50";
+    for spec_yaml in [
+        UNVERSIONED_SPEC_YAML.to_owned(),
+        UNVERSIONED_SPEC_YAML.replace("class: pod\n", "class: pod\nschema: 1\n"),
+    ] {
+        let store = store_test_with(|store| store.with_migration_rewrite(true))?;
+        write_old_pod(&store, hash, &spec_yaml)?;
+
+        let pod = store.load_pod(&ModelID::Latest("style-transfer".to_owned()))?;
+        assert_eq!(pod.hash, hash, "Upgraded pod should keep its original hash");
+        assert_eq!(to_yaml(&pod)?, to_yaml(&get_test_pod()?)?);
+        assert_eq!(
+            fs::read_to_string(store.make_path::<Pod>(hash, "spec.yaml"))?,
+            to_yaml(&pod)?,
+            "spec.yaml should be rewritten with the current schema version"
+        );
+        assert_eq!(
+            fs::read_to_string(store.make_path::<Pod>(hash, "original/spec.yaml"))?,
+            spec_yaml,
+            "The spec should be kept as it was hashed"
+        );
+
+        // The rewritten spec is verified through the original
+        assert_eq!(store.load_pod(&ModelID::Hash(hash.to_owned()))?.hash, hash);
+        fs::write(
+            store.make_path::<Pod>(hash, "spec.yaml"),
+            to_yaml(&pod)?.replace("recommended_cpus: 250m", "recommended_cpus: 500m"),
+        )?;
+        assert!(
+            store.load_pod(&ModelID::Hash(hash.to_owned())).is_err(),
+            "A tampered rewritten spec should fail verification"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_tampered_spec_fails_hash_verification() -> Result<()> {
+    let store = store_test(None)?;
+    let pod = get_test_pod()?;
+    store.save_pod(&pod)?;
+    let spec_path = store.make_path::<Pod>(&pod.hash, "spec.yaml");
+    fs::write(
+        &spec_path,
+        to_yaml(&pod)?.replace("recommended_cpus: 250m", "recommended_cpus: 500m"),
+    )?;
+
+    for error in [
+        store
+            .load_pod(&ModelID::Latest("style-transfer".to_owned()))
+            .expect_err("Loading a tampered spec should fail"),
+        store
+            .load_pod(&ModelID::Hash(pod.hash.clone()))
+            .expect_err("Loading a tampered spec by hash should fail"),
+        store
+            .save_pod(&pod)
+            .expect_err("Saving over a tampered spec should fail"),
+    ] {
+        assert!(
+            error.to_string().contains("hashes to `sha256:")
+                && error
+                    .to_string()
+                    .contains(&format!("instead of `{}`", pod.hash)),
+            "Unexpected error: {error}"
+        );
+    }
+
+    let unverified_store = LocalFileStore::new(store.get_directory()).with_hash_verification(false);
+    assert_eq!(
+        unverified_store
+            .load_pod(&ModelID::Hash(pod.hash.clone()))?
+            .hash,
+        pod.hash,
+        "Verification can be turned off"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_load_pod_from_json_and_toml_specs() -> Result<()> {
+    let store = store_test(None)?;
+    let pod = get_test_pod()?;
+    store.save_pod(&pod)?;
+    let yaml_path = store.make_path::<Pod>(&pod.hash, "spec.yaml");
+    let mut unannotated_pod = pod.clone();
+    unannotated_pod.annotation = None;
+
+    for (file_name, spec) in [("spec.json", to_json(&pod)?), ("spec.toml", to_toml(&pod)?)] {
+        let spec_path = store.make_path::<Pod>(&pod.hash, file_name);
+        fs::remove_file(&yaml_path)?;
+        fs::write(&spec_path, spec)?;
+        assert_eq!(
+            store.load_pod(&ModelID::Latest("style-transfer".to_owned()))?,
+            pod,
+            "Pod should load from `{file_name}`"
+        );
+
+        store.save_pod(&unannotated_pod)?;
+        assert!(
+            !yaml_path.exists(),
+            "Saving should not duplicate `{file_name}` as spec.yaml"
+        );
+        fs::rename(&spec_path, &yaml_path)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_pod_secret_refs_are_stored_by_name() -> Result<()> {
+    let store = store_test(None)?;
+    let pod = get_test_pod_builder()?
+        .secret_env("HF_TOKEN", SecretRef::new("hf-token"))
+        .build()?;
+    store.save_pod(&pod)?;
+
+    let spec_yaml = fs::read_to_string(store.make_path::<Pod>(&pod.hash, "spec.yaml"))?;
+    assert!(
+        !spec_yaml.contains("HF_TOKEN"),
+        "Unhashed secret references should not be in spec.yaml"
+    );
+    let annotation = pod.annotation.clone().expect("Test pod is annotated");
+    assert_eq!(
+        fs::read_to_string(store.make_secrets_path::<Pod>(
+            &pod.hash,
+            &annotation.name,
+            &annotation.version
+        ))?,
+        "HF_TOKEN: hf-token\n",
+        "Secret references should be stored by name along with the annotation"
+    );
+    assert_eq!(
+        store.load_pod(&ModelID::Latest("style-transfer".to_owned()))?,
+        pod
+    );
+
+    // Same hash, different secrets
+    let mut other_pod = get_test_pod_builder()?
+        .secret_env("HF_TOKEN", SecretRef::new("other-token"))
+        .build()?;
+    other_pod.annotation = Some(Annotation {
+        version: Version::new(0, 68, 0),
+        ..annotation
+    });
+    assert_eq!(other_pod.hash, pod.hash);
+    store.save_pod(&other_pod)?;
+    assert_eq!(
+        store.load_pod(&ModelID::Latest("style-transfer".to_owned()))?,
+        other_pod
+    );
+    assert_eq!(
+        store.load_pod(&ModelID::NameVer(
+            "style-transfer".to_owned(),
+            Version::new(0, 67, 0)
+        ))?,
+        pod
+    );
+
+    let error = store
+        .load_pod(&ModelID::Hash(pod.hash.clone()))
+        .expect_err("Loading by hash should not drop the secret references");
+    assert!(
+        error.to_string().contains("has to be loaded by name"),
+        "Unexpected error message: {error}"
+    );
+    store.delete_annotation::<Pod>("style-transfer", &Version::new(0, 67, 0))?;
+    store.delete_annotation::<Pod>("style-transfer", &Version::new(0, 68, 0))?;
+    assert_eq!(
+        store.load_pod(&ModelID::Hash(pod.hash.clone()))?.hash,
+        pod.hash,
+        "Without secret references left the pod should load by hash"
+    );
+
+    let mut unannotated_pod = other_pod.clone();
+    unannotated_pod.annotation = None;
+    let error = store
+        .save_pod(&unannotated_pod)
+        .expect_err("Secret references should not be dropped silently");
+    assert!(
+        error
+            .to_string()
+            .contains("no annotation to store them with"),
+        "Unexpected error message: {error}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_rebuild_index_rejects_invalid_version() -> Result<()> {
+    let store = store_test(None)?;
+    let pod = get_test_pod()?;
+    store.save_pod(&pod)?;
+    fs::write(
+        store.make_path::<Pod>(&pod.hash, "annotations/style-transfer-0.10.yaml"),
+        "name: style-transfer\nversion: '0.10'\ndescription: ''\n",
+    )?;
+
+    let error = store
+        .rebuild_index::<Pod>()
+        .expect_err("Rebuilding the index should fail on a malformed version");
+    assert!(
+        error
+            .to_string()
+            .contains("`0.10` is not a valid semantic version"),
+        "Unexpected error message: {error}"
+    );
+    Ok(())
 }
 
 #[expect(clippy::too_many_lines, reason = "This will be cut down later")]
-fn test_item_store_with_annotation(item_type: &ModelType) -> Result<()> {
+fn test_item_store_with_annotation<T: Model + Clone + PartialEq>(item: &T) -> Result<()> {
     let store_directory = tempdir()?.path().to_string_lossy().to_string();
     {
-        let item = get_test_item(item_type)?;
-        let mut store = store_test(Some(&tempdir()?.path().to_string_lossy()))?; // new tests can just call store_test(None)?
+        let store = store_test(Some(&tempdir()?.path().to_string_lossy()))?; // new tests can just call store_test(None)?
+        let annotation = item
+            .annotation()
+            .ok_or_else(|| anyhow!("Test item should be annotated"))?;
+        let name_ver = ModelID::NameVer(annotation.name.clone(), annotation.version.clone());
 
-        let annotation_file = store.make_annotation_path(&item);
+        let annotation_file = store.make_annotation_path(item);
 
-        let spec_file = store.make_path(item_type, item.get_hash(), "spec.yaml");
+        let spec_file = store.make_path::<T>(item.hash(), "spec.yaml");
 
         // Test save
-        store.save_model(&item)?;
+        store.save(item)?;
         assert!(
             spec_file.exists(),
             "Spec file could not be found after item creation"
         );
         assert_eq!(
             fs::read_to_string(&spec_file)?,
-            item.to_yaml()?,
+            to_yaml(item)?,
             "spec.yaml does not match item.to_yaml, something went wrong during the write"
         );
         assert!(
@@ -40,102 +853,88 @@ fn test_item_store_with_annotation(item_type: &ModelType) -> Result<()> {
         );
 
         // Test load
-        let loaded_item = store.load_model(
-            item_type,
-            &ModelID::NameVer(item.get_name().into(), item.get_version().into()),
-        )?;
+        let loaded_item = store.load::<T>(&name_ver)?;
 
         assert!(
-            loaded_item == item,
+            loaded_item == *item,
             "Loaded item does not match the item was saved"
         );
 
-        let loaded_item_by_hash =
-            store.load_model(item_type, &ModelID::Hash(item.get_hash().into()))?;
+        let loaded_item_by_hash = store.load::<T>(&ModelID::Hash(item.hash().into()))?;
 
         assert!(
-            loaded_item_by_hash.is_annotation_none(),
+            loaded_item_by_hash.annotation().is_none(),
             "Annotation should be empty"
         );
 
         // Test list pod
         // Should only return a result of 1
-        let items = store.list_model(item_type)?;
+        let items = store.list::<T>()?;
         assert!(items.len() == 1, "List item should be length of 1");
         assert!(
-            items[0].name == item.get_name(),
-            "Item name from list_model didn't match what was saved"
+            items[0].name == annotation.name,
+            "Item name from list didn't match what was saved"
         );
         assert!(
-            items[0].version == item.get_version(),
-            "Item version from list_model didn't match what was saved"
+            items[0].version == annotation.version,
+            "Item version from list didn't match what was saved"
         );
         assert!(
-            items[0].hash == item.get_hash(),
-            "Item hash from list_model didn't match what was saved"
+            items[0].hash == item.hash(),
+            "Item hash from list didn't match what was saved"
         );
 
         // Add another pod with a new version
-        let mut item_2 = item.clone();
-        item_2.set_name("Second Item Test");
+        let item_2 = renamed(item, "Second Item Test")?;
 
-        store.save_model(&item_2)?;
+        store.save(&item_2)?;
 
         assert!(
-            store.list_model(item_type)?.len() == 2,
+            store.list::<T>()?.len() == 2,
             "List item should be length of 2"
         );
 
         // Test delete
-        store.delete_item_annotation(item_type, item_2.get_name(), item_2.get_version())?;
+        store.delete_annotation::<T>("Second Item Test", &annotation.version)?;
 
         assert!(
-            store.list_model(item_type)?.len() == 1,
+            store.list::<T>()?.len() == 1,
             "List item should be length of 1"
         );
 
         // Delete the first pod
-        store.delete_item(
-            item_type,
-            &ModelID::NameVer(item.get_name().into(), item.get_version().into()),
-        )?;
+        store.delete::<T>(&name_ver)?;
 
-        assert!(
-            store.list_model(item_type)?.is_empty(),
-            "List item should be empty"
-        );
+        assert!(store.list::<T>()?.is_empty(), "List item should be empty");
 
         // Test the case with where delete wipes out all annotation
-        store.save_model(&item)?;
-        store.save_model(&item_2)?;
+        store.save(item)?;
+        store.save(&item_2)?;
 
         assert!(
-            store.list_model(item_type)?.len() == 2,
+            store.list::<T>()?.len() == 2,
             "List item should be length of 2"
         );
 
         // Delete the entire pod which should get rid of annotation
-        store.delete_item(
-            item_type,
-            &ModelID::NameVer(item.get_name().into(), item.get_version().into()),
-        )?;
+        store.delete::<T>(&name_ver)?;
 
-        assert!(store.list_model(item_type)?.is_empty(), "List item should be empty after deleting the object itself regardless of how many annotations there are");
+        assert!(store.list::<T>()?.is_empty(), "List item should be empty after deleting the object itself regardless of how many annotations there are");
 
         // Test the hash version
         // Test the case with where delete wipes out all annotation
-        store.save_model(&item)?;
-        store.save_model(&item_2)?;
+        store.save(item)?;
+        store.save(&item_2)?;
 
         assert!(
-            store.list_model(item_type)?.len() == 2,
+            store.list::<T>()?.len() == 2,
             "List item should be length of 2"
         );
 
         // Delete the entire pod which should get rid of annotation
-        store.delete_item(item_type, &ModelID::Hash(item.get_hash().into()))?;
+        store.delete::<T>(&ModelID::Hash(item.hash().into()))?;
 
-        assert!(store.list_model(item_type)?.is_empty(), "List item should be empty after deleting the object itself regardless of how many annotations there are");
+        assert!(store.list::<T>()?.is_empty(), "List item should be empty after deleting the object itself regardless of how many annotations there are");
 
         assert!(
             !spec_file.exists(),
@@ -152,3 +951,160 @@ fn test_item_store_with_annotation(item_type: &ModelType) -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn test_save_pod_enforces_pin_policy() -> Result<()> {
+    let store = store_test_with(|store| store.with_pin_policy(PinPolicy::Require))?;
+    let message = store
+        .save_pod(&get_test_pod()?)
+        .expect_err("Pod with a mutable tag should be refused")
+        .to_string();
+    for expected in [
+        "Found 2 problems",
+        "Image `zenmldocker/zenml-server:0.67.0` is not pinned to an `@sha256:` digest",
+        "names `0.67.0` instead of a full commit SHA",
+    ] {
+        assert!(
+            message.contains(expected),
+            "Expected `{expected}` in error message: {message}"
+        );
+    }
+    assert!(
+        store.save(&get_test_pod()?).is_err(),
+        "The generic save should enforce the pin policy on pods too"
+    );
+
+    let pinned_pod = get_test_pod_builder()?
+        .source_commit_url(
+            "https://github.com/zenml-io/zenml/commit/8a3a5ba8f4c5d3e2b1a0f9e8d7c6b5a4f3e2d1c0",
+        )
+        .image(concat!(
+            "zenmldocker/zenml-server:0.67.0",
+            "@sha256:7d1e2a9bfb4e5b4c3e1f0a5d2c8b6e4f3a2d1c0b9a8f7e6d5c4b3a2918273645"
+        ))
+        .build()?;
+    store.save_pod(&pinned_pod)?;
+    assert_eq!(
+        store
+            .load_pod(&ModelID::Hash(pinned_pod.hash.clone()))?
+            .image_reference()?,
+        pinned_pod.image_reference()?
+    );
+
+    let mut legacy_value = serde_yaml::to_value(get_test_pod()?)?;
+    legacy_value["source_commit_url"] = "zenml-io/zenml@0.67.0".into();
+    let mut legacy_pod = serde_yaml::from_value::<Pod>(legacy_value)?;
+    legacy_pod.hash = HashAlgorithm::default().digest(&to_canonical(&legacy_pod)?);
+    assert!(
+        store_test(None)?.save_pod(&legacy_pod).is_err(),
+        "The default policy should still report an unparsable source"
+    );
+    store_test_with(|store| store.with_pin_policy(PinPolicy::Allow))?.save_pod(&legacy_pod)?;
+    Ok(())
+}
+
+#[test]
+fn test_list_pod_by_labels() -> Result<()> {
+    let store = store_test(None)?;
+    let pod = get_test_pod()?;
+    store.save_pod(&pod)?;
+    let mut dev_pod = pod.clone();
+    dev_pod.annotation = pod.annotation.clone().map(|annotation| Annotation {
+        version: Version::new(0, 68, 0),
+        labels: BTreeMap::from([
+            ("team".to_owned(), "vision".to_owned()),
+            ("stage".to_owned(), "dev".to_owned()),
+        ]),
+        created_at: None,
+        ..annotation
+    });
+    store.save_pod(&dev_pod)?;
+    let mut audio_pod = pod.clone();
+    audio_pod.annotation = pod.annotation.clone().map(|annotation| Annotation {
+        name: "speech-to-text".to_owned(),
+        labels: BTreeMap::from([("team".to_owned(), "audio".to_owned())]),
+        ..annotation
+    });
+    store.save_pod(&audio_pod)?;
+
+    for (selector, expected) in [
+        (
+            "team=vision",
+            vec!["style-transfer:0.67.0", "style-transfer:0.68.0"],
+        ),
+        ("team=vision,stage=prod", vec!["style-transfer:0.67.0"]),
+        (
+            "stage!=prod",
+            vec!["speech-to-text:0.67.0", "style-transfer:0.68.0"],
+        ),
+        (
+            "stage",
+            vec!["style-transfer:0.67.0", "style-transfer:0.68.0"],
+        ),
+        ("team=robotics", vec![]),
+    ] {
+        assert_eq!(
+            store
+                .list_pod_by_labels(&selector.parse()?)?
+                .into_iter()
+                .map(|model_info| format!("{}:{}", model_info.name, model_info.version))
+                .collect::<Vec<_>>(),
+            expected,
+            "Unexpected pods for `{selector}`"
+        );
+    }
+
+    let loaded_pod = store.load_pod(&ModelID::Latest("style-transfer".to_owned()))?;
+    assert_eq!(loaded_pod.annotation, dev_pod.annotation);
+    assert_eq!(
+        loaded_pod.hash, pod.hash,
+        "Annotation metadata should not affect the hash"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_label_selector_rejects_malformed_terms() {
+    for (selector, reason) in [
+        ("team=vision,", "every term needs a label key"),
+        ("=vision", "every term needs a label key"),
+        ("team==vision", "compares `team` more than once"),
+    ] {
+        let message = selector
+            .parse::<LabelSelector>()
+            .expect_err("Selector should be rejected")
+            .to_string();
+        assert!(
+            message.contains(reason),
+            "Expected `{reason}` for `{selector}`: {message}"
+        );
+    }
+}
+
+#[test]
+fn test_malformed_hash_prefix_is_rejected() -> Result<()> {
+    let store = store_test(None)?;
+    let pod = get_test_pod()?;
+    store.save_pod(&pod)?;
+
+    for prefix in ["", "..", "a/../b", "sha256:", ":13d6"] {
+        let error = store
+            .delete_pod(&ModelID::Hash(prefix.to_owned()))
+            .expect_err("Malformed prefix should be rejected");
+        assert!(
+            error.to_string().contains("is not a digest"),
+            "Unexpected error message for `{prefix}`: {error}"
+        );
+        assert!(
+            store.load_pod(&ModelID::Hash(prefix.to_owned())).is_err(),
+            "Malformed prefix `{prefix}` should not load anything"
+        );
+    }
+    assert_eq!(
+        store.load_pod(&ModelID::Hash(pod.hash.clone()))?.hash,
+        pod.hash,
+        "Store should be untouched"
+    );
+    assert_eq!(store.list_pod()?.len(), 1, "Index should be untouched");
+    Ok(())
+}